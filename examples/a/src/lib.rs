@@ -1,7 +1,7 @@
 use b::B;
 use ioc::Bean;
 use ioc::prelude::*;
-use ioc::with;
+use ioc::{inject, with};
 
 pub trait A {
     fn test(&self);
@@ -13,8 +13,7 @@ pub struct AKey;
 pub struct SomeNeedA;
 
 impl SomeNeedA {
-    #[with(bean(path = B))]
-    #[with(alias(name = AKey, traits = A))]
+    #[with(bean(path = B), alias(name = AKey, traits = A))]
     pub fn test2<C>(&self, ctx: &C)
     where
         C: Context,
@@ -25,7 +24,7 @@ impl SomeNeedA {
     }
 
     #[with(bean(path = B))]
-    #[with(alias(context = Cxx, name = AKey, traits = A))]
+    #[with(alias(name = AKey, traits = A))]
     pub fn test<Cxx>(&self, ctx: &Cxx)
     where
         Cxx: Context,
@@ -37,3 +36,21 @@ impl SomeNeedA {
         b.test2(ctx);
     }
 }
+
+#[with(bean(path = B))]
+pub trait NeedB {
+    fn print_b<C: Context>(&self, ctx: &C);
+}
+
+#[with(bean(path = B))]
+impl NeedB for SomeNeedA {
+    fn print_b<C: Context>(&self, ctx: &C) {
+        println!("{}", ctx.get_by_key::<B>().test());
+    }
+}
+
+#[inject]
+pub fn handle(#[bean] b: &B, #[alias(AKey)] a: &impl A, greeting: String) {
+    println!("{greeting}: {}", b.test());
+    a.test();
+}
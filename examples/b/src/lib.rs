@@ -2,8 +2,9 @@ use ioc::Bean;
 use ioc::prelude::*;
 
 #[derive(Debug, Bean)]
+#[rivete(config_prefix = "bbb")]
 pub struct B {
-    #[rivete(config(name = "bbb.name"))]
+    #[rivete(config)]
     name: String,
 }
 
@@ -26,7 +27,11 @@ mod tests {
     fn test_b() {
         env_logger::init();
 
-        let ctx = Ctx::new().unwrap();
+        let ctx = Ctx::from_cfg(CfgParams {
+            args: Some(&[]),
+            ..CfgParams::default()
+        })
+        .unwrap();
         let x = &ctx;
 
         let b = x.get_by_key::<B>();
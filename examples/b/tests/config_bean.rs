@@ -0,0 +1,79 @@
+use b as _;
+use ioc::prelude::*;
+use ioc::{Config, ConfigBean};
+use std::collections::HashMap;
+
+#[derive(Debug, PartialEq, Config)]
+pub struct Tls {
+    cert: String,
+    #[rivete(default = true)]
+    verify: bool,
+}
+
+#[derive(Debug, ConfigBean)]
+#[rivete(prefix = "server")]
+pub struct Server {
+    host: String,
+    #[rivete(default = 8080)]
+    port: u16,
+    #[rivete(name = "allowed-origins")]
+    origins: Vec<String>,
+    headers: HashMap<String, String>,
+    tls: Option<Tls>,
+    proxy: Option<String>,
+}
+
+#[test]
+fn binds_the_whole_subtree() {
+    let dir = std::env::temp_dir().join(format!("ioc-config-bean-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("app.toml"),
+        concat!(
+            "[bbb]\nname = \"b\"\n",
+            "[server]\nhost = \"localhost\"\nallowed-origins = [\"a.com\", \"b.com\"]\n",
+            "[server.headers]\nx-frame-options = \"DENY\"\n",
+            "[server.tls]\ncert = \"cert.pem\"\n",
+        ),
+    )
+    .unwrap();
+
+    let dir = dir.display().to_string();
+    let ctx = &Ctx::from_cfg(CfgParams {
+        dir: &dir,
+        args: Some(&[]),
+        ..CfgParams::default()
+    })
+    .unwrap();
+
+    let server = ctx.get_by_key::<Server>();
+    assert_eq!(server.host, "localhost");
+    assert_eq!(server.port, 8080);
+    assert_eq!(server.origins, ["a.com", "b.com"]);
+    assert_eq!(server.headers["x-frame-options"], "DENY");
+    assert_eq!(
+        server.tls,
+        Some(Tls {
+            cert: "cert.pem".to_string(),
+            verify: true,
+        })
+    );
+    assert_eq!(server.proxy, None);
+
+    let keys: Vec<_> = ioc::schema::entries()
+        .into_iter()
+        .filter(|entry| entry.bean == "Server")
+        .map(|entry| entry.key)
+        .collect();
+    assert_eq!(
+        keys,
+        [
+            "server.allowed-origins",
+            "server.headers",
+            "server.host",
+            "server.port",
+            "server.proxy",
+            "server.tls",
+        ]
+    );
+}
@@ -0,0 +1,46 @@
+use b as _;
+use ioc::prelude::*;
+use ioc::{Config, ConfigBean};
+
+#[allow(dead_code)]
+#[derive(Debug, Config)]
+pub struct Tls {
+    cert: String,
+    verify: bool,
+}
+
+// never built, `tls.cert` is missing
+#[allow(dead_code)]
+#[derive(Debug, ConfigBean)]
+#[rivete(prefix = "server")]
+pub struct Server {
+    host: String,
+    port: u16,
+    tls: Tls,
+}
+
+#[test]
+fn issues_name_the_failing_field() {
+    let sources = ConfigSources::new().toml(
+        "app.toml",
+        "[bbb]\nname = 'b'\n[server]\nhost = 'localhost'\nport = 80\n[server.tls]\nverify = true\n",
+    );
+
+    let err = Ctx::from_sources(sources).unwrap_err();
+    let Error::InvalidConfig(issues) = &err else {
+        panic!("unexpected error: {err}");
+    };
+
+    let issue = issues.iter().find(|issue| issue.bean == "Server").unwrap();
+    assert_eq!(issue.field, "tls");
+    assert_eq!(issue.key, "server.tls.cert");
+    assert_eq!(issue.ty, std::any::type_name::<Tls>());
+    assert!(matches!(
+        issue.cause,
+        Error::MissingConfig {
+            bean: "Server",
+            field: "tls",
+            ..
+        }
+    ));
+}
@@ -0,0 +1,69 @@
+use b as _;
+use ioc::prelude::*;
+use ioc::{Bean, Config};
+
+// only looked at through the report
+#[allow(dead_code)]
+#[derive(Debug, Config)]
+pub struct Admin {
+    user: String,
+    password: Secret<String>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Bean)]
+pub struct Db {
+    #[rivete(config(name = "db.url"))]
+    url: String,
+    #[rivete(config(name = "db.pool", default = 4))]
+    pool: i32,
+    #[rivete(config(name = "db.password"))]
+    password: Secret<String>,
+    #[rivete(config(name = "db.admin"))]
+    admin: Admin,
+}
+
+#[test]
+fn report_shows_origins() {
+    let sources = ConfigSources::new()
+        .env_vars(
+            "ioc_report_test",
+            [("IOC_REPORT_TEST_DB_URL", "postgres://env")],
+        )
+        .toml(
+            "app.toml",
+            concat!(
+                "[bbb]\nname = 'b'\n[db]\nurl = 'postgres://file'\npassword = 'hunter2'\n",
+                "[db.admin]\nuser = 'root'\npassword = 'hunter3'\n",
+            ),
+        );
+    let ctx = &Ctx::from_sources(sources).unwrap();
+
+    let report = ctx.config_report();
+    let db: Vec<_> = report
+        .entries
+        .iter()
+        .filter(|entry| entry.key.starts_with("db."))
+        .collect();
+    assert_eq!(
+        db.iter()
+            .map(|entry| (entry.key, entry.value.as_deref(), entry.origin.to_string()))
+            .collect::<Vec<_>>(),
+        [
+            (
+                "db.admin",
+                Some("{ password = ***, user = \"root\" }"),
+                "app.toml".to_string()
+            ),
+            ("db.password", Some("***"), "app.toml".to_string()),
+            ("db.pool", Some("4"), "default".to_string()),
+            (
+                "db.url",
+                Some("\"postgres://env\""),
+                "env:IOC_REPORT_TEST_*".to_string()
+            ),
+        ]
+    );
+    let report = report.to_string();
+    assert!(!report.contains("hunter2") && !report.contains("hunter3"));
+}
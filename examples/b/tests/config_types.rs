@@ -0,0 +1,50 @@
+use b as _;
+use ioc::Bean;
+use ioc::prelude::*;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+#[derive(Debug, Bean)]
+pub struct WebConfig {
+    #[rivete(config(name = "web.addr"))]
+    addr: SocketAddr,
+    #[rivete(config(name = "web.graceful_shutdown_timeout"))]
+    shutdown_timeout: HumanDuration,
+    #[rivete(config(name = "web.max_body"))]
+    max_body: ByteSize,
+    #[rivete(config(name = "web.static"))]
+    assets: ConfigPath,
+    #[rivete(config(name = "web.upstream"))]
+    upstream: Url,
+}
+
+#[test]
+fn human_readable_values() {
+    let dir = std::env::temp_dir().join(format!("ioc-config-types-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("app.toml"),
+        concat!(
+            "[bbb]\nname = \"b\"\n",
+            "[web]\naddr = \"0.0.0.0:8080\"\ngraceful_shutdown_timeout = \"1m 30s\"\n",
+            "max_body = \"10MiB\"\nstatic = \"public\"\nupstream = \"http://api.local:9000/v1\"\n",
+        ),
+    )
+    .unwrap();
+
+    let dir_str = dir.display().to_string();
+    let ctx = &Ctx::from_cfg(CfgParams {
+        dir: &dir_str,
+        args: Some(&[]),
+        ..CfgParams::default()
+    })
+    .unwrap();
+
+    let web = ctx.get_by_key::<WebConfig>();
+    assert_eq!(web.addr.port(), 8080);
+    assert_eq!(*web.shutdown_timeout, Duration::from_secs(90));
+    assert_eq!(web.max_body.as_u64(), 10 << 20);
+    assert_eq!(web.assets.0, dir.join("public"));
+    assert_eq!(web.upstream.host(), "api.local");
+    assert_eq!(web.upstream.port(), Some(9000));
+}
@@ -0,0 +1,47 @@
+use b as _;
+use ioc::Bean;
+use ioc::prelude::*;
+use std::path::PathBuf;
+
+fn home_dir(ctx: &impl ConfigCollector, file: &str) -> Result<PathBuf> {
+    Ok(ctx.env_value::<PathBuf>("IOC_ENV_TEST_HOME")?.join(file))
+}
+
+#[derive(Debug, Bean)]
+pub struct Node {
+    #[rivete(env = "IOC_ENV_TEST_HOSTNAME")]
+    hostname: String,
+    #[rivete(env = "IOC_ENV_TEST_TIMEOUT")]
+    timeout: HumanDuration,
+    #[rivete(env = "IOC_ENV_TEST_UNSET")]
+    zone: Option<String>,
+    #[rivete(value = std::process::id())]
+    pid: u32,
+    #[rivete(value = home_dir(ctx, ".node"))]
+    state: PathBuf,
+    #[rivete(value = format!("{}:{}", ctx.get_config::<String>("bbb.name").unwrap(), 80))]
+    address: String,
+}
+
+#[test]
+fn env_and_computed_fields() {
+    let sources = ConfigSources::new()
+        .memory("test", [("bbb.name", "b")])
+        .env_vars(
+            "APP",
+            [
+                ("IOC_ENV_TEST_HOSTNAME", "node-1"),
+                ("IOC_ENV_TEST_TIMEOUT", "1m30s"),
+                ("IOC_ENV_TEST_HOME", "/home/node"),
+            ],
+        );
+    let ctx = &Ctx::from_sources(sources).unwrap();
+
+    let node = ctx.get_by_key::<Node>();
+    assert_eq!(node.hostname, "node-1");
+    assert_eq!(node.timeout.as_secs(), 90);
+    assert_eq!(node.zone, None);
+    assert_eq!(node.pid, std::process::id());
+    assert_eq!(node.state, PathBuf::from("/home/node/.node"));
+    assert_eq!(node.address, "b:80");
+}
@@ -0,0 +1,43 @@
+use b as _;
+use ioc::Bean;
+use ioc::prelude::*;
+
+#[derive(Debug, Bean)]
+pub struct Web {
+    #[rivete(config(name = "web.host"))]
+    host: String,
+    #[rivete(config(name = "web.port"))]
+    port: u16,
+    #[rivete(config(name = "web.tls"))]
+    tls: bool,
+}
+
+#[test]
+fn yaml_and_json_files() {
+    let dir = std::env::temp_dir().join(format!("ioc-formats-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let write = |file: &str, content: &str| std::fs::write(dir.join(file), content).unwrap();
+    write(
+        "app.yaml",
+        "bbb:\n  name: b\nweb:\n  host: yaml\n  port: 80\n",
+    );
+    write(
+        "app.json",
+        r#"{"web": {"host": "json", "port": 8080, "tls": true}}"#,
+    );
+    write("app.local.yml", "web:\n  port: 8443\n");
+
+    let dir = dir.display().to_string();
+    let ctx = &Ctx::from_cfg(CfgParams {
+        dir: &dir,
+        args: Some(&[]),
+        ..CfgParams::default()
+    })
+    .unwrap();
+
+    // yaml wins over json for the same file, the local file over both
+    let web = ctx.get_by_key::<Web>();
+    assert_eq!(web.host, "yaml");
+    assert_eq!(web.port, 8443);
+    assert!(web.tls);
+}
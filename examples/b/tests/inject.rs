@@ -0,0 +1,59 @@
+use b::B;
+use ioc::prelude::*;
+use ioc::{Bean, inject};
+use std::ops::Deref;
+
+pub trait Greeter {
+    fn greet(&self, name: &str) -> String;
+}
+
+pub struct GreeterKey;
+
+/// Aliases are declared on a context type, not on `&Ctx` itself.
+pub struct Scope(&'static Ctx);
+
+impl Deref for Scope {
+    type Target = Ctx;
+
+    fn deref(&self) -> &Ctx {
+        self.0
+    }
+}
+
+#[derive(Debug, Bean)]
+#[rivete(alias(name = GreeterKey, ctx = Scope))]
+pub struct Polite;
+
+impl Greeter for Polite {
+    fn greet(&self, name: &str) -> String {
+        format!("good day, {name}")
+    }
+}
+
+#[inject]
+fn greet(#[bean] b: &B, #[alias(GreeterKey)] greeter: &impl Greeter, name: &str) -> String {
+    format!("{} / {}", b.test(), greeter.greet(name))
+}
+
+#[inject]
+fn greet_dyn(#[alias(GreeterKey)] greeter: &dyn Greeter, times: usize) -> Vec<String> {
+    (0..times).map(|_| greeter.greet("you")).collect()
+}
+
+#[inject]
+fn name_len(#[bean] b: &B, #[bean] polite: &Polite) -> usize {
+    let _ = polite;
+    b.test().len()
+}
+
+#[test]
+fn inject_into_free_functions() {
+    let sources = ConfigSources::new().memory("test", [("bbb.name", "b")]);
+    let ctx: &'static Ctx = Box::leak(Box::new(Ctx::from_sources(sources).unwrap()));
+
+    assert_eq!(name_len(&ctx), "hello this is b".len());
+
+    let scope = Scope(ctx);
+    assert_eq!(greet(&scope, "ann"), "hello this is b / good day, ann");
+    assert_eq!(greet_dyn(&scope, 2), ["good day, you", "good day, you"]);
+}
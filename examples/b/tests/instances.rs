@@ -0,0 +1,58 @@
+use b as _;
+use ioc::Bean;
+use ioc::prelude::*;
+
+pub struct ReplicaDb;
+
+#[derive(Debug, Bean)]
+#[rivete(config_prefix = "db.primary", instance(key = ReplicaDb, config_prefix = "db.replica"))]
+pub struct Db {
+    #[rivete(config)]
+    url: String,
+    #[rivete(config(name = "pool.size", default = 4))]
+    pool_size: i32,
+}
+
+#[test]
+fn instances_read_their_own_prefix() {
+    let dir = std::env::temp_dir().join(format!("ioc-instances-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("app.toml"),
+        concat!(
+            "[bbb]\nname = \"b\"\n",
+            "[db.primary]\nurl = \"pg://primary\"\npool.size = 16\n",
+            "[db.replica]\nurl = \"pg://replica\"\n",
+        ),
+    )
+    .unwrap();
+
+    let dir = dir.display().to_string();
+    let ctx = &Ctx::from_cfg(CfgParams {
+        dir: &dir,
+        args: Some(&[]),
+        ..CfgParams::default()
+    })
+    .unwrap();
+
+    let primary = ctx.get_by_key::<Db>();
+    assert_eq!(primary.url, "pg://primary");
+    assert_eq!(primary.pool_size, 16);
+
+    let replica = ctx.get_by_key::<ReplicaDb>();
+    assert_eq!(replica.url, "pg://replica");
+    assert_eq!(replica.pool_size, 4);
+
+    let keys: Vec<_> = ioc::schema::entries()
+        .into_iter()
+        .filter(|entry| entry.field == "url")
+        .map(|entry| (entry.bean, entry.key))
+        .collect();
+    assert_eq!(
+        keys,
+        [
+            ("Db", "db.primary.url"),
+            ("Db(ReplicaDb)", "db.replica.url")
+        ]
+    );
+}
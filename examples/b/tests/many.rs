@@ -0,0 +1,33 @@
+use b::B;
+use ioc::Bean;
+use ioc::prelude::*;
+
+#[derive(Debug, Bean)]
+pub struct Counter;
+
+impl Counter {
+    fn count(&self, text: &str) -> usize {
+        text.len()
+    }
+}
+
+#[derive(Debug, Bean)]
+pub struct Prefix;
+
+#[test]
+fn many_beans_at_once() {
+    let sources = ConfigSources::new().memory("test", [("bbb.name", "b")]);
+    let ctx = &Ctx::from_sources(sources).unwrap();
+
+    let (b, counter, prefix) = ctx.get_many::<(B, Counter, Prefix)>();
+    assert!(std::ptr::eq(b, ctx.get_by_key::<B>()));
+    assert_eq!(counter.count(b.test()), 15);
+    assert!(std::ptr::eq(prefix, ctx.get_by_key::<Prefix>()));
+
+    let (counter,) = ctx.get_many::<(Counter,)>();
+    assert_eq!(counter.count("abc"), 3);
+
+    let len = ctx.call(|b: &B, counter: &Counter| counter.count(b.test()));
+    assert_eq!(len, 15);
+    assert_eq!(ctx.call(|| 7), 7);
+}
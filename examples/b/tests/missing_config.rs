@@ -0,0 +1,30 @@
+use b as _;
+use ioc::prelude::*;
+
+#[test]
+fn missing_config_is_reported() {
+    let param = CfgParams {
+        dir: "tests",
+        args: Some(&[]),
+        ..CfgParams::default()
+    };
+
+    let err = Ctx::from_cfg(param).unwrap_err();
+    let Error::InvalidConfig(issues) = &err else {
+        panic!("unexpected error: {err}");
+    };
+
+    let issue = issues.iter().find(|issue| issue.bean == "B").unwrap();
+    assert_eq!(issue.field, "name");
+    assert_eq!(issue.key, "bbb.name");
+    assert_eq!(issue.env, "APP_BBB_NAME");
+    assert!(matches!(
+        issue.cause,
+        Error::MissingConfig {
+            bean: "B",
+            field: "name",
+            ..
+        }
+    ));
+    assert!(err.to_string().contains(std::any::type_name::<String>()));
+}
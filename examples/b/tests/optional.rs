@@ -0,0 +1,35 @@
+use b as _;
+use ioc::prelude::*;
+use ioc::{Bean, Config};
+
+#[derive(Debug, PartialEq, Config)]
+pub struct Tls {
+    cert: String,
+    #[rivete(default = true)]
+    verify: bool,
+}
+
+#[derive(Debug, Bean)]
+pub struct Web {
+    #[rivete(config(name = "web.host"))]
+    host: Option<String>,
+    #[rivete(config(name = "web.proxy"))]
+    proxy: Option<Url>,
+    #[rivete(config(name = "web.port", optional, validate(range(min = 1))))]
+    port: Option<u16>,
+    #[rivete(config(name = "web.tls"))]
+    tls: Option<Tls>,
+}
+
+#[test]
+fn missing_keys_are_none() {
+    let sources =
+        ConfigSources::new().toml("app.toml", "[bbb]\nname = 'b'\n[web]\nhost = 'localhost'\n");
+    let ctx = &Ctx::from_sources(sources).unwrap();
+
+    let web = ctx.get_by_key::<Web>();
+    assert_eq!(web.host.as_deref(), Some("localhost"));
+    assert!(web.proxy.is_none());
+    assert_eq!(web.port, None);
+    assert_eq!(web.tls, None);
+}
@@ -0,0 +1,48 @@
+use b as _;
+use ioc::prelude::*;
+use ioc::{Bean, Config};
+
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Config)]
+pub struct Tls {
+    cert: String,
+    verify: bool,
+}
+
+// never built, every field is broken
+#[allow(dead_code)]
+#[derive(Debug, Bean)]
+pub struct Web {
+    #[rivete(config(name = "web.port"))]
+    port: Option<u16>,
+    #[rivete(config(name = "web.workers", validate(range(min = 1))))]
+    workers: Option<i32>,
+    #[rivete(config(name = "web.tls"))]
+    tls: Option<Tls>,
+}
+
+#[test]
+fn set_but_broken_keys_are_errors() {
+    let sources = ConfigSources::new().toml(
+        "app.toml",
+        "[bbb]\nname = 'b'\n[web]\nport = 'http'\nworkers = 0\n[web.tls]\nverify = true\n",
+    );
+
+    let err = Ctx::from_sources(sources).unwrap_err();
+    let Error::InvalidConfig(issues) = &err else {
+        panic!("unexpected error: {err}");
+    };
+    let broken: Vec<_> = issues
+        .iter()
+        .filter(|issue| issue.bean == "Web")
+        .map(|issue| (issue.field, issue.key.as_str()))
+        .collect();
+    assert_eq!(
+        broken,
+        [
+            ("port", "web.port"),
+            ("workers", "web.workers"),
+            ("tls", "web.tls.cert"),
+        ]
+    );
+}
@@ -0,0 +1,83 @@
+use b as _;
+use ioc::prelude::*;
+use ioc::{Bean, with};
+use std::fs::{File, write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Bean)]
+#[rivete(refresh)]
+pub struct Greeting {
+    #[rivete(config(name = "greeting.text"))]
+    text: String,
+}
+
+/// A refresh bean is registered as `Refresh<Greeting>`, which `with` is told.
+#[with(bean(path = Greeting, refresh))]
+fn greeting<C: Context>(ctx: &C) -> String {
+    ctx.get_by_key::<Greeting>().load().text.clone()
+}
+
+fn write_config(path: &Path, greeting: Option<&str>, age: u64) {
+    let mut content = String::from("[bbb]\nname = \"b\"\n");
+    if let Some(text) = greeting {
+        content.push_str(&format!("[greeting]\ntext = \"{text}\"\n"));
+    }
+    write(path, content).unwrap();
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(age))
+        .unwrap();
+}
+
+#[test]
+fn refresh_beans_on_reload() {
+    let dir = std::env::temp_dir().join(format!("ioc-refresh-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("app.toml");
+    write_config(&file, Some("hello"), 1);
+
+    let dir = dir.display().to_string();
+    let ctx = Arc::new(
+        Ctx::from_cfg(CfgParams {
+            dir: &dir,
+            args: Some(&[]),
+            ..CfgParams::default()
+        })
+        .unwrap(),
+    );
+    let text = || ctx.get_by_key::<Greeting>().load().text.clone();
+    assert_eq!(text(), "hello");
+    assert_eq!(greeting(&ctx), "hello");
+
+    let snapshot = ctx.get_by_key::<Greeting>().load();
+    write_config(&file, Some("bye"), 2);
+    ctx.reload_config().unwrap();
+    assert_eq!(text(), "bye");
+    assert_eq!(snapshot.text, "hello");
+
+    write_config(&file, None, 3);
+    assert!(matches!(ctx.reload_config(), Err(Error::InvalidConfig(_))));
+    assert_eq!(text(), "bye");
+
+    assert!(!ctx.poll_config().unwrap());
+    write_config(&file, Some("polled"), 4);
+    assert!(ctx.poll_config().unwrap());
+    assert_eq!(text(), "polled");
+
+    let watcher = ConfigWatcher::spawn(ctx.clone(), Duration::from_millis(10), |err| {
+        panic!("{err}")
+    });
+    write_config(&file, Some("watched"), 5);
+    for _ in 0..200 {
+        if ctx.get_by_key::<Greeting>().load().text == "watched" {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    drop(watcher);
+    assert_eq!(ctx.get_by_key::<Greeting>().load().text, "watched");
+}
@@ -0,0 +1,47 @@
+use b as _;
+use ioc::prelude::*;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+/// A stand-in for the store's `?recurse` endpoint, answering with `body`.
+fn serve(body: Arc<Mutex<String>>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut line = String::new();
+            let mut reader = BufReader::new(&stream);
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let body = body.lock().unwrap().clone();
+            write!(stream, "HTTP/1.0 200 OK\r\n\r\n{body}").unwrap();
+        }
+    });
+    endpoint
+}
+
+#[test]
+fn remote_changes_are_polled() {
+    // `b` and `c`, base64 encoded
+    let body = Arc::new(Mutex::new(
+        r#"[{"Key":"app/bbb/name","Value":"Yg=="}]"#.to_string(),
+    ));
+    let endpoint = serve(body.clone());
+
+    let sources = ConfigSources::new().provider(RemoteSource::new(&endpoint, "app").unwrap());
+    let ctx = &Ctx::from_sources(sources).unwrap();
+    let name = || {
+        let report = ctx.config_report();
+        let entry = report.entries.iter().find(|entry| entry.key == "bbb.name");
+        entry.unwrap().value.clone().unwrap()
+    };
+    assert_eq!(name(), "\"b\"");
+    assert!(!ctx.poll_config().unwrap());
+
+    *body.lock().unwrap() = r#"[{"Key":"app/bbb/name","Value":"Yw=="}]"#.to_string();
+    assert!(ctx.poll_config().unwrap());
+    assert_eq!(name(), "\"c\"");
+}
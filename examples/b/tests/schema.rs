@@ -0,0 +1,45 @@
+use b as _;
+use ioc::Bean;
+
+// only looked at through the schema
+#[allow(dead_code)]
+#[derive(Debug, Bean)]
+pub struct Limits {
+    #[rivete(config(name = "limits.max", default = 1_000u32))]
+    max: u32,
+    #[rivete(config(name = "limits.separator", default = ','))]
+    separator: String,
+    #[rivete(config(name = "limits.root", default = r#""c:\\data""#))]
+    root: String,
+}
+
+#[test]
+fn sample_lists_linked_beans() {
+    let entry = ioc::schema::entries()
+        .into_iter()
+        .find(|entry| entry.bean == "B")
+        .unwrap();
+    assert_eq!(entry.key, "bbb.name");
+    assert_eq!(entry.ty(), std::any::type_name::<String>());
+
+    assert!(
+        ioc::schema::sample_toml()
+            .contains("[bbb]\n# B.name (alloc::string::String)\nname = \"\"\n")
+    );
+    assert!(ioc::schema::json_schema().contains(r#""required":["bbb"]"#));
+}
+
+#[test]
+fn defaults_are_json_values() {
+    let schema = ioc::schema::json_schema();
+    assert!(schema.contains(r#""description":"Limits.max (u32)","default":1000}"#));
+    assert!(
+        schema
+            .contains(r#""description":"Limits.separator (alloc::string::String)","default":","}"#)
+    );
+    assert!(
+        schema.contains(
+            r#""description":"Limits.root (alloc::string::String)","default":"c:\\data"}"#
+        )
+    );
+}
@@ -0,0 +1,37 @@
+use b as _;
+use ioc::Bean;
+use ioc::prelude::*;
+
+#[derive(Debug, Bean)]
+pub struct Db {
+    #[rivete(config(name = "db.user"))]
+    user: String,
+    #[rivete(config(name = "db.password", validate(non_empty)))]
+    password: Secret<String>,
+}
+
+#[test]
+fn secrets_stay_out_of_debug_output() {
+    let path = std::env::temp_dir().join(format!("ioc-db-password-{}", std::process::id()));
+    std::fs::write(&path, "hunter2\n").unwrap();
+
+    let sources = ConfigSources::new().memory(
+        "test",
+        [
+            ("bbb.name", "b".to_string()),
+            ("db.user", "app".to_string()),
+            ("db.password", format!("file:{}", path.display())),
+        ],
+    );
+    let ctx = &Ctx::from_sources(sources).unwrap();
+
+    let db = ctx.get_by_key::<Db>();
+    assert_eq!(db.user, "app");
+    assert_eq!(db.password.expose(), "hunter2");
+    assert_eq!(
+        format!("{db:?}"),
+        "Db { user: \"app\", password: Secret(***) }"
+    );
+
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,60 @@
+use b as _;
+use ioc::Bean;
+use ioc::prelude::*;
+use serde::Deserialize;
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct Retry {
+    attempts: u32,
+    #[serde(default, with = "humantime_like")]
+    backoff_ms: u64,
+}
+
+/// Accepts `"250ms"` as well as a plain number.
+mod humantime_like {
+    use serde::{Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<u64, D::Error> {
+        let s = String::deserialize(d)?;
+        s.trim_end_matches("ms")
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Bean)]
+pub struct Client {
+    #[rivete(config(name = "client.retry"), serde)]
+    retry: Retry,
+    #[rivete(config(name = "client.tags", default = Vec::new()), serde)]
+    tags: Vec<String>,
+}
+
+#[test]
+fn serde_fields() {
+    let dir = std::env::temp_dir().join(format!("ioc-serde-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("app.toml"),
+        "[bbb]\nname = \"b\"\n[client.retry]\nattempts = 3\nbackoff_ms = \"250ms\"\n",
+    )
+    .unwrap();
+
+    let dir = dir.display().to_string();
+    let ctx = &Ctx::from_cfg(CfgParams {
+        dir: &dir,
+        args: Some(&[]),
+        ..CfgParams::default()
+    })
+    .unwrap();
+
+    let client = ctx.get_by_key::<Client>();
+    assert_eq!(
+        client.retry,
+        Retry {
+            attempts: 3,
+            backoff_ms: 250,
+        }
+    );
+    assert!(client.tags.is_empty());
+}
@@ -0,0 +1,30 @@
+use b::B;
+use ioc::prelude::*;
+
+/// Stands in for a secrets store or any other user-written provider.
+struct Vault;
+
+impl ConfigProvider for Vault {
+    fn name(&self) -> &str {
+        "vault"
+    }
+
+    fn load(&self, values: &mut ConfigSourceBuilder<'_>) -> ioc::Result<()> {
+        values.set("bbb.name", "from vault");
+        Ok(())
+    }
+}
+
+#[test]
+fn compose_sources() {
+    let sources = ConfigSources::new()
+        .args(["--bbb.name=from args"])
+        .provider(Vault)
+        .toml("defaults", "[bbb]\nname = 'from toml'\n");
+
+    let ctx = &Ctx::from_sources(sources).unwrap();
+    assert_eq!(
+        format!("{:?}", ctx.get_by_key::<B>()),
+        r#"B { name: "from args" }"#
+    );
+}
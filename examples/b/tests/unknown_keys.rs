@@ -0,0 +1,48 @@
+use b as _;
+use ioc::Bean;
+use ioc::prelude::*;
+
+#[derive(Debug, Default, Bean)]
+#[rivete(watch = "log.level")]
+pub struct LogPatcher;
+
+impl OnConfigChange for LogPatcher {
+    fn on_config_change(&self, _: &str, _: Option<&str>, _: Option<&str>) {}
+}
+
+// only built for the keys it reads
+#[allow(dead_code)]
+#[derive(Debug, Bean)]
+pub struct Upstream {
+    #[rivete(value = ctx.get_config::<String>("upstream.host").unwrap_or_default())]
+    host: String,
+}
+
+#[test]
+fn typos_fail_when_denied() {
+    let sources = ConfigSources::new().unknown_keys(UnknownKeys::Deny).toml(
+        "app.toml",
+        concat!(
+            "[bbb]\nname = 'b'\nnmae = 'x'\n",
+            "[log]\nlevel = 'info'\n",
+            "[upstream]\nhost = 'api.local'\n",
+        ),
+    );
+
+    let err = Ctx::from_sources(sources).unwrap_err();
+    let Error::UnknownConfigKeys(unknown) = &err else {
+        panic!("unexpected error: {err}");
+    };
+    assert_eq!(
+        unknown,
+        &[UnknownKey {
+            key: "bbb.nmae".to_string(),
+            source: "app.toml".to_string(),
+            suggestion: Some("bbb.name"),
+        }]
+    );
+    assert_eq!(
+        err.to_string(),
+        "unknown config key `bbb.nmae` in app.toml, did you mean `bbb.name`?"
+    );
+}
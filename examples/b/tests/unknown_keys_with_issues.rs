@@ -0,0 +1,29 @@
+use b as _;
+use ioc::prelude::*;
+
+#[test]
+fn typos_are_reported_with_the_issues() {
+    let sources = ConfigSources::new()
+        .unknown_keys(UnknownKeys::Deny)
+        .toml("app.toml", "[bbb]\nnmae = 'b'\n");
+
+    let err = Ctx::from_sources(sources).unwrap_err();
+    let Error::InvalidConfig(issues) = &err else {
+        panic!("unexpected error: {err}");
+    };
+
+    let issue = issues.iter().find(|issue| issue.bean == "B").unwrap();
+    assert_eq!(issue.key, "bbb.name");
+    assert_eq!(
+        issues.unknown_keys(),
+        &[UnknownKey {
+            key: "bbb.nmae".to_string(),
+            source: "app.toml".to_string(),
+            suggestion: Some("bbb.name"),
+        }]
+    );
+    assert!(
+        err.to_string()
+            .ends_with("\n  - unknown config key `bbb.nmae` in app.toml, did you mean `bbb.name`?")
+    );
+}
@@ -0,0 +1,77 @@
+use b as _;
+use ioc::Bean;
+use ioc::prelude::{ConfigSources, Ctx, Error};
+
+fn even(value: &i32) -> Result<(), String> {
+    match value % 2 {
+        0 => Ok(()),
+        _ => Err("must be even".to_string()),
+    }
+}
+
+mod rules {
+    pub fn absolute(value: &str) -> Result<(), String> {
+        match value.starts_with('/') {
+            true => Ok(()),
+            false => Err("must start with `/`".to_string()),
+        }
+    }
+}
+
+// never built, every field breaks its rule
+#[allow(dead_code)]
+#[derive(Debug, Bean)]
+pub struct Web {
+    #[rivete(config(name = "web.port", validate(range(min = 1, max = 1024))))]
+    port: u16,
+    #[rivete(config(name = "web.host", validate(non_empty, regex = "[a-z.]+")))]
+    host: String,
+    #[rivete(config(name = "web.mode", validate(one_of("dev", "prod"))))]
+    mode: String,
+    #[rivete(config(name = "web.workers", default = 4, validate(custom = even)))]
+    workers: i32,
+    #[rivete(config(name = "web.backlog", default = 7, validate(custom = Self::small)))]
+    backlog: i32,
+    #[rivete(config(name = "web.root", validate(custom = rules::absolute)))]
+    root: Option<String>,
+}
+
+impl Web {
+    fn small(value: &i32) -> Result<(), String> {
+        match *value < 5 {
+            true => Ok(()),
+            false => Err("must be below 5".to_string()),
+        }
+    }
+}
+
+#[test]
+fn violations_are_reported() {
+    let sources = ConfigSources::new().toml(
+        "test",
+        "[bbb]\nname = 'b'\n[web]\nport = 0\nhost = 'Example.com'\nmode = 'staging'\nworkers = 3\n\
+         root = 'srv'\n",
+    );
+
+    let err = Ctx::from_sources(sources).unwrap_err();
+    let Error::InvalidConfig(issues) = &err else {
+        panic!("unexpected error: {err}");
+    };
+
+    let causes: Vec<_> = issues
+        .iter()
+        .filter(|issue| issue.bean == "Web")
+        .map(|issue| issue.cause.to_string())
+        .collect();
+    assert_eq!(
+        causes,
+        [
+            "config key `web.port` = `0` violates range(1..=1024)",
+            "config key `web.host` = `\"Example.com\"` violates regex(\"[a-z.]+\")",
+            "config key `web.mode` = `\"staging\"` violates one_of([\"dev\", \"prod\"])",
+            "config key `web.workers` = `3` violates even: must be even",
+            "config key `web.backlog` = `7` violates Self::small: must be below 5",
+            "config key `web.root` = `\"srv\"` violates rules::absolute: must start with `/`",
+        ]
+    );
+}
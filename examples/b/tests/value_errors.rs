@@ -0,0 +1,30 @@
+use b as _;
+use ioc::Bean;
+use ioc::prelude::*;
+
+// never built, `port` fails to compute
+#[allow(dead_code)]
+#[derive(Debug, Bean)]
+pub struct Listener {
+    #[rivete(value = "http".parse::<u16>())]
+    port: u16,
+}
+
+#[test]
+fn failed_values_are_issues() {
+    let sources = ConfigSources::new().memory("test", [("bbb.name", "b")]);
+    let err = Ctx::from_sources(sources).unwrap_err();
+    let Error::InvalidConfig(issues) = &err else {
+        panic!("unexpected error: {err}");
+    };
+
+    let issue = issues
+        .iter()
+        .find(|issue| issue.bean == "Listener")
+        .unwrap();
+    assert_eq!(issue.field, "port");
+    assert_eq!(
+        issue.to_string(),
+        "bean `Listener` field `port` as `u16`: invalid digit found in string"
+    );
+}
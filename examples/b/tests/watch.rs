@@ -0,0 +1,83 @@
+use b as _;
+use ioc::Bean;
+use ioc::prelude::*;
+use std::sync::{Mutex, OnceLock};
+
+type Change = (String, Option<String>, Option<String>);
+
+static CTX: OnceLock<&'static Ctx> = OnceLock::new();
+
+#[derive(Debug, Default, Bean)]
+#[rivete(watch = "log.level", watch = "log.format", watch = "log.filters")]
+pub struct LogPatcher {
+    changes: Mutex<Vec<Change>>,
+}
+
+impl OnConfigChange for LogPatcher {
+    fn on_config_change(&self, key: &str, old: Option<&str>, new: Option<&str>) {
+        // the config lock is released while watchers run
+        if let Some(ctx) = CTX.get() {
+            assert!(!ctx.config_report().entries.is_empty());
+        }
+        self.changes.lock().unwrap().push((
+            key.to_string(),
+            old.map(str::to_string),
+            new.map(str::to_string),
+        ));
+    }
+}
+
+#[test]
+fn notify_changed_keys() {
+    let dir = std::env::temp_dir().join(format!("ioc-watch-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let write = |filters: &str| {
+        std::fs::write(
+            dir.join("app.toml"),
+            format!(
+                "[bbb]\nname = \"b\"\n[log]\nlevel = \"info\"\nformat = \"json\"\n\
+                 [log.filters]\n{filters}\n"
+            ),
+        )
+        .unwrap()
+    };
+    write("hyper = \"warn\"");
+
+    let dir = dir.display().to_string();
+    let ctx: &'static Ctx = Box::leak(Box::new(
+        Ctx::from_cfg(CfgParams {
+            dir: &dir,
+            args: Some(&[]),
+            ..CfgParams::default()
+        })
+        .unwrap(),
+    ));
+    CTX.set(ctx).unwrap();
+    let changes = || std::mem::take(&mut *ctx.get_by_key::<LogPatcher>().changes.lock().unwrap());
+
+    assert!(!ctx.poll_config().unwrap());
+    ctx.reload_config().unwrap();
+    assert!(changes().is_empty());
+
+    unsafe { std::env::set_var("APP_LOG_LEVEL", "debug") };
+    assert!(ctx.poll_config().unwrap());
+    assert_eq!(
+        changes(),
+        [(
+            "log.level".to_string(),
+            Some("info".to_string()),
+            Some("debug".to_string())
+        )]
+    );
+
+    write("hyper = \"warn\"\nsqlx = \"error\"");
+    ctx.reload_config().unwrap();
+    assert_eq!(
+        changes(),
+        [(
+            "log.filters".to_string(),
+            Some("{ hyper = \"warn\" }".to_string()),
+            Some("{ hyper = \"warn\", sqlx = \"error\" }".to_string())
+        )]
+    );
+}
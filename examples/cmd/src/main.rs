@@ -1,5 +1,5 @@
 use crate::mod2::AliasHaha;
-use a::{A, AKey, SomeNeedA};
+use a::{A, AKey, SomeNeedA, handle};
 use ioc::Bean;
 use ioc::prelude::*;
 
@@ -68,6 +68,8 @@ pub mod mod2 {
 
 fn main() {
     use crate::Ctx as Root;
+    // config keys can be overridden with `--key=value` or `-D key=value`,
+    // and `--config <path>` / `--profile <name>` pick the config files
     let ctx = Root::new().unwrap();
 
     // let _ctx = module::new(ctx);
@@ -90,4 +92,14 @@ fn main() {
 
     some_need_a.test(&ctx);
     some_need_a.test2(&ctx);
+
+    handle(&ctx, "injected".to_string());
+
+    let (some_need_a, a_impl) = ctx.get_many::<(SomeNeedA, AImplByMain)>();
+    println!("{some_need_a:?} {a_impl:?}");
+
+    ctx.call(|some_need_a: &SomeNeedA, a_impl: &AImplByMain| {
+        some_need_a.test(&ctx);
+        a_impl.test();
+    });
 }
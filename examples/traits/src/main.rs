@@ -1,7 +1,7 @@
 use std::any::type_name;
 
 pub trait Has<Key> {
-    fn get() -> () {
+    fn get() {
         println!(
             "Got key :{} from module {}",
             type_name::<Key>(),
@@ -1,10 +1,23 @@
 pub mod prelude {
     pub use ::ioc_core::{
-        Alias, Context, Ctx, Registered, Result, config::*, error::Error, init::*, life::*,
-        link::*, place::*,
+        Alias, Context, Ctx, Registered, Result,
+        config::*,
+        error::Error,
+        init::*,
+        life::*,
+        many::{Beans, Handler},
+        link::*,
+        place::*,
+        refresh::*,
+        schema::{ConfigDefault, ConfigEntry},
+        source::*,
+        validate,
     };
 }
 
 pub use prelude::Result;
 
+pub use ::ioc_core::cfg_rs;
+pub use ::ioc_core::schema;
+
 pub use ioc_macros::*;
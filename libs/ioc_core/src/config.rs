@@ -1,17 +1,223 @@
+use crate::source::{ArgsSource, CommandLine, ConfigSources, Loaded};
 use cfg_rs::{Configuration, FromConfig};
-use std::fmt::Debug;
+use std::collections::BTreeSet;
+use std::fmt::{Debug, Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+#[cfg(feature = "serde")]
+mod de;
+#[cfg(feature = "serde")]
+pub use de::Serde;
+
+mod report;
+pub(crate) use report::Rendered;
+pub use report::{ConfigReport, Origin, ReportEntry};
+
+mod unknown;
+pub use unknown::{UnknownKey, UnknownKeys};
+
+mod secret;
+pub use secret::{IsSecret, NotSecret, Secret, SecretProbe};
+
+mod types;
+pub use types::{ByteSize, ConfigPath, HumanDuration, Url};
 
 pub trait IsConfig: FromConfig {}
 
 impl<T> IsConfig for T where T: FromConfig {}
 
+/// What a `#[rivete(value = ...)]` expression evaluates to: the field itself,
+/// or a `Result` whose error is reported as a [`ConfigIssue`].
+pub trait FieldValue<T> {
+    fn into_result(self) -> crate::Result<T>;
+}
+
+impl<T> FieldValue<T> for T {
+    fn into_result(self) -> crate::Result<T> {
+        Ok(self)
+    }
+}
+
+impl<T, E: Into<Box<dyn std::error::Error>>> FieldValue<T> for Result<T, E> {
+    fn into_result(self) -> crate::Result<T> {
+        use crate::error::Error;
+
+        self.map_err(|err| match err.into().downcast::<Error>() {
+            Ok(err) => *err,
+            Err(err) => Error::Other(anyhow::anyhow!("{err}")),
+        })
+    }
+}
+
 pub trait ConfigSource {
     fn get_config<T: IsConfig>(&self, key: impl AsRef<str>) -> crate::Result<T>;
     fn get_config_or<T: IsConfig>(&self, key: impl AsRef<str>, default: T) -> crate::Result<T>;
+
+    /// `None` if nothing is set at or below `key`; a value that does not
+    /// parse, or a table missing one of its keys, is still an error.
+    fn get_optional<T: IsConfig>(&self, key: impl AsRef<str>) -> crate::Result<Option<T>> {
+        use crate::error::Error;
+        use cfg_rs::ConfigError::ConfigNotFound;
+
+        let key = key.as_ref();
+        match self.get_config::<T>(key) {
+            Ok(value) => Ok(Some(value)),
+            Err(Error::ConfigError(ConfigNotFound(_)))
+                if matches!(
+                    self.get_config::<report::Rendered>(key),
+                    Err(Error::ConfigError(ConfigNotFound(_)))
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// A [`ConfigSource`] used while building beans, which records failed field
+/// lookups instead of failing fast so every broken key is reported at once.
+pub trait ConfigCollector: ConfigSource {
+    fn collect(&mut self, issue: ConfigIssue);
+
+    /// The environment variable that can supply `key`.
+    fn env_var(&self, key: &str) -> String;
+
+    /// Parses the env variable `name` like [`env_value`], from the
+    /// environment the config was loaded with.
+    fn env_value<T: IsConfig>(&self, name: &str) -> crate::Result<T>;
+
+    fn config_field<T>(
+        &mut self,
+        bean: &'static str,
+        field: &'static str,
+        key: &str,
+        value: crate::Result<T>,
+    ) -> Option<T> {
+        match value {
+            Ok(value) => Some(value),
+            Err(cause) => {
+                // A struct read as a whole may miss a key below `key`.
+                let key = match &cause {
+                    crate::error::Error::ConfigError(cfg_rs::ConfigError::ConfigNotFound(
+                        missing,
+                    )) => missing.clone(),
+                    _ => key.to_string(),
+                };
+                let env = self.env_var(&key);
+                self.collect(ConfigIssue::new::<T>(bean, field, &key, env, cause));
+                None
+            }
+        }
+    }
+
+    /// Like [`config_field`](Self::config_field), for a `#[derive(ConfigBean)]`
+    /// struct read as a whole from `key`: the issue names the field whose key
+    /// failed, as recorded in the bean's schema entries.
+    fn config_struct<T>(
+        &mut self,
+        bean: &'static str,
+        key: &str,
+        value: crate::Result<T>,
+    ) -> Option<T> {
+        use crate::error::Error;
+        use cfg_rs::ConfigError::{ConfigNotFound, ConfigParseError, ConfigTypeMismatch};
+
+        let cause = match value {
+            Ok(value) => return Some(value),
+            Err(cause) => cause,
+        };
+        let key = match &cause {
+            Error::ConfigError(
+                ConfigNotFound(failed)
+                | ConfigParseError(failed, _)
+                | ConfigTypeMismatch(failed, ..),
+            ) => failed.clone(),
+            _ => key.to_string(),
+        };
+        let entry = crate::schema::entries().into_iter().find(|entry| {
+            entry.bean == bean
+                && key
+                    .strip_prefix(entry.key)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+        });
+        let env = self.env_var(&key);
+        let issue = match entry {
+            Some(entry) => ConfigIssue {
+                ty: (entry.ty)(),
+                ..ConfigIssue::new::<T>(bean, entry.field, &key, env, cause)
+            },
+            // not tied to a key, so reported against the bean itself
+            None => ConfigIssue::new::<T>(bean, bean, &key, env, cause),
+        };
+        self.collect(issue);
+        None
+    }
+
+    /// Like [`config_field`](Self::config_field), for the result of a
+    /// `#[rivete(value = ...)]` expression, which has no key.
+    fn value_field<T>(
+        &mut self,
+        bean: &'static str,
+        field: &'static str,
+        value: crate::Result<T>,
+    ) -> Option<T> {
+        match value {
+            Ok(value) => Some(value),
+            Err(cause) => {
+                self.collect(ConfigIssue::new::<T>(bean, field, "", String::new(), cause));
+                None
+            }
+        }
+    }
+
+    /// Like [`config_field`](Self::config_field), for a value read from the
+    /// env variable `name` by [`env_value`].
+    fn env_field<T>(
+        &mut self,
+        bean: &'static str,
+        field: &'static str,
+        name: &str,
+        value: crate::Result<T>,
+    ) -> Option<T> {
+        use crate::error::Error;
+
+        match value {
+            Ok(value) => Some(value),
+            Err(cause) => {
+                let cause = match cause {
+                    Error::ConfigError(cfg_rs::ConfigError::ConfigNotFound(_)) => {
+                        Error::MissingEnv {
+                            bean,
+                            field,
+                            name: name.to_string(),
+                        }
+                    }
+                    cause => cause,
+                };
+                self.collect(ConfigIssue::new::<T>(
+                    bean,
+                    field,
+                    name,
+                    name.to_string(),
+                    cause,
+                ));
+                None
+            }
+        }
+    }
 }
 
 pub struct CfgSource {
     conf: Configuration,
+    /// Each provider on its own, loaded with `conf`.
+    layers: Vec<(String, Configuration)>,
+    /// The keys read as a [`Secret`] so far.
+    secrets: Mutex<BTreeSet<String>>,
+    /// The keys read so far, known to the check of [`UnknownKeys`].
+    read_keys: Mutex<BTreeSet<String>>,
+    sources: ConfigSources,
 }
 impl Debug for CfgSource {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -19,11 +225,47 @@ impl Debug for CfgSource {
     }
 }
 
+/// Where the config is read from.
+///
+/// Sources, from the highest precedence to the lowest:
+///
+/// 1. command line overrides, `--bbb.name=foo` or `-D bbb.name=foo`, from `args`;
+/// 2. env variables starting with `{prefix_env}_`, e.g. `APP_BBB_NAME` for `bbb.name`;
+/// 3. with `dotenv`, the same variables in `{dir}/.env.{profile}` then `{dir}/.env`;
+/// 4. `{dir}/{name}.local.toml`, a developer override kept out of version control;
+/// 5. `{dir}/{name}-{profile}.toml`, when a profile is active;
+/// 6. `{dir}/{name}.toml`.
+///
+/// Every file is optional and may use the `.tml` extension instead, or with
+/// the `yaml` and `json` features `.yaml`, `.yml` or `.json`. When several
+/// extensions exist for one file, the first in that order wins.
+///
+/// The env variables `{prefix_env}_APP_NAME`, `{prefix_env}_APP_DIR` and
+/// `{prefix_env}_APP_PROFILE` replace `name`, `dir` and `profile`, even when
+/// they are set in code; `{prefix_env}_PROFILE` is accepted for the profile as
+/// well. This is new: before, the values set in code always won, so a binary
+/// that relied on that now picks up these variables. On the command
+/// line, `--config <dir>/<name>.toml` replaces `dir` and `name` and loads that
+/// exact file, which must exist, and `--profile <profile>` replaces `profile`,
+/// over the env variables. The values in use are readable as the `app.name`,
+/// `app.dir` and `app.profile` keys, and `dir` is the base dir of
+/// [`ConfigPath`] values.
 #[derive(Debug)]
 pub struct CfgParams<'a> {
     pub name: &'a str,
     pub dir: &'a str,
     pub prefix_env: &'a str,
+    /// The active profile, `None` for just the base files.
+    pub profile: Option<&'a str>,
+    /// The command line, `std::env::args` without the program name if unset;
+    /// tests pass `Some(&[])` to keep the arguments of the test harness out.
+    pub args: Option<&'a [String]>,
+    /// The environment, `std::env::vars` if unset.
+    pub vars: Option<&'a [(String, String)]>,
+    /// What to do with keys that no bean reads.
+    pub unknown_keys: UnknownKeys,
+    /// Reads `.env` files from `dir`, leaving the process environment alone.
+    pub dotenv: bool,
 }
 
 impl Default for CfgParams<'static> {
@@ -32,30 +274,591 @@ impl Default for CfgParams<'static> {
             name: "app",
             dir: ".",
             prefix_env: "APP",
+            profile: None,
+            args: None,
+            vars: None,
+            unknown_keys: UnknownKeys::Warn,
+            dotenv: false,
         }
     }
 }
 
-impl CfgSource {
-    pub(crate) fn new(param: CfgParams) -> crate::Result<Self> {
-        let conf = Configuration::with_predefined_builder()
-            .set_prefix_env(param.prefix_env)
-            .set_name(param.name)
-            .set_dir(param.dir)
-            .init()?;
+/// Extensions of the config files, in the order they are looked up; the
+/// `yaml` and `json` features add `yaml`, `yml` and `json`, in that order.
+const EXTENSIONS: &[&str] = &[
+    "toml",
+    "tml",
+    #[cfg(feature = "yaml")]
+    "yaml",
+    #[cfg(feature = "yaml")]
+    "yml",
+    #[cfg(feature = "json")]
+    "json",
+];
+
+impl CfgParams<'_> {
+    /// The sources described above, in priority order.
+    pub fn sources(&self) -> ConfigSources {
+        let command_line = match self.args {
+            Some(args) => CommandLine::parse(args),
+            None => CommandLine::parse(std::env::args().skip(1)),
+        };
+
+        let vars: Vec<(String, String)> = match self.vars {
+            Some(vars) => vars.to_vec(),
+            None => std::env::vars().collect(),
+        };
+        let prefix_env = self.prefix_env.to_uppercase();
+        let var = |name: &str| {
+            let name = format!("{prefix_env}_{name}");
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.clone())
+                .filter(|value| !value.is_empty())
+        };
+
+        let (dir, name) = match &command_line.config {
+            Some(path) => (
+                path.parent().unwrap_or(Path::new("")).to_path_buf(),
+                path.file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| self.name.to_string()),
+            ),
+            None => (
+                PathBuf::from(var("APP_DIR").as_deref().unwrap_or(self.dir)),
+                var("APP_NAME").unwrap_or_else(|| self.name.to_string()),
+            ),
+        };
+
+        let profile = command_line
+            .profile
+            .clone()
+            .filter(|profile| !profile.is_empty())
+            .or_else(|| var("APP_PROFILE"))
+            .or_else(|| var("PROFILE"))
+            .or_else(|| self.profile.map(str::to_string))
+            .filter(|profile| !profile.is_empty());
+
+        let mut app = vec![
+            ("app.name", name.clone()),
+            ("app.dir", dir.to_string_lossy().into_owned()),
+        ];
+        if let Some(profile) = &profile {
+            app.push(("app.profile", profile.clone()));
+        }
+
+        // an explicit `--config` file is loaded as is, and must exist; its
+        // overlays only use its extension
+        let config = command_line.config.clone();
+        let extensions = match config.as_ref().and_then(|path| path.extension()) {
+            Some(ext) => vec![ext.to_string_lossy().into_owned()],
+            None => EXTENSIONS.iter().map(|ext| ext.to_string()).collect(),
+        };
+        let mut stems = vec![format!("{name}.local")];
+        if let Some(profile) = &profile {
+            stems.push(format!("{name}-{profile}"));
+        }
+        if config.is_none() {
+            stems.push(name);
+        }
 
+        let mut sources = ConfigSources::new()
+            .unknown_keys(self.unknown_keys)
+            .base_dir(&dir)
+            .provider(ArgsSource::from(command_line))
+            .memory("app", app);
+        sources = match self.vars {
+            Some(vars) => sources.env_vars(&prefix_env, vars.iter().cloned()),
+            None => sources.env(&prefix_env),
+        };
+        if self.dotenv {
+            if let Some(profile) = &profile {
+                sources = sources.dotenv(dir.join(format!(".env.{profile}")), &prefix_env);
+            }
+            sources = sources.dotenv(dir.join(".env"), &prefix_env);
+        }
+        for stem in stems {
+            for ext in &extensions {
+                sources = sources.file(dir.join(format!("{stem}.{ext}")));
+            }
+        }
+        if let Some(config) = config {
+            sources = sources.required_file(config);
+        }
+        sources
+    }
+}
+
+impl CfgSource {
+    pub(crate) fn new(sources: ConfigSources) -> crate::Result<Self> {
+        let Loaded { conf, layers } = sources.load()?;
         Ok(Self {
             conf,
+            layers,
+            secrets: Mutex::default(),
+            read_keys: Mutex::default(),
+            sources,
         })
     }
+
+    /// Loads every source again from scratch, keeping the keys known to hold
+    /// a [`Secret`] as beans that are not refreshed do not read them again.
+    pub(crate) fn reopen(&self) -> crate::Result<Self> {
+        let fresh = Self::new(self.sources.clone())?;
+        let secrets = self.secrets.lock().unwrap_or_else(PoisonError::into_inner);
+        *fresh.secrets.lock().unwrap_or_else(PoisonError::into_inner) = secrets.clone();
+        Ok(fresh)
+    }
+
+    /// Whether a source changed since the last check, e.g. a config file or a
+    /// prefixed env variable was created, changed or removed; each change is
+    /// only reported once.
+    pub(crate) fn changed(&mut self) -> bool {
+        self.sources.changed()
+    }
+
+    /// The environment variable that can supply `key`, e.g. `APP_BBB_NAME` for
+    /// `bbb.name`; empty without an env source.
+    pub fn env_var(&self, key: &str) -> String {
+        match self.sources.prefix_env() {
+            Some(prefix) => env_var(prefix, key),
+            None => String::new(),
+        }
+    }
+
+    /// Parses the env variable `name` like [`env_value`], from the variables
+    /// given to [`ConfigSources::env_vars`] if any.
+    pub fn env_value<T: IsConfig>(&self, name: &str) -> crate::Result<T> {
+        parse_value(name, self.sources.var(name))
+    }
+}
+
+/// Parses the env variable `name` like a config value, so any [`IsConfig`]
+/// type can be read from it; an unset variable is a missing key. The value is
+/// taken as is, a `${...}` in it is not a placeholder.
+pub fn env_value<T: IsConfig>(name: &str) -> crate::Result<T> {
+    parse_value(name, std::env::var(name).ok())
+}
+
+/// Parses `value` as the key `name`, escaped so that cfg-rs reads it back
+/// verbatim instead of expanding placeholders; `None` is a missing key.
+fn parse_value<T: IsConfig>(name: &str, value: Option<String>) -> crate::Result<T> {
+    let mut values = Configuration::new().register_kv("env");
+    if let Some(value) = value {
+        let escaped = value
+            .replace('\\', "\\\\")
+            .replace('$', "\\$")
+            .replace('}', "\\}");
+        values = values.set(name, escaped);
+    }
+    Ok(values.finish()?.get::<T>(name)?)
+}
+
+pub(crate) fn env_var(prefix: &str, key: &str) -> String {
+    let key: String = key
+        .chars()
+        .filter(|c| *c != ']')
+        .map(|c| match c {
+            '.' | '[' => '_',
+            c => c.to_ascii_uppercase(),
+        })
+        .collect();
+    format!("{prefix}_{key}")
+}
+
+impl CfgSource {
+    /// Reads `key` from `conf` with the base dir of [`ConfigPath`] values,
+    /// noting the keys read and the ones read as a [`Secret`].
+    fn read<T>(
+        &self,
+        key: &str,
+        read: impl FnOnce(&Configuration) -> Result<T, cfg_rs::ConfigError>,
+    ) -> crate::Result<T> {
+        self.read_keys
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key.to_string());
+        let dir = self.sources.base_dir_path();
+        let mut secrets = self.secrets.lock().unwrap_or_else(PoisonError::into_inner);
+        Ok(types::with_base_dir(dir, || {
+            secret::with_secret_keys(&mut secrets, || read(&self.conf))
+        })?)
+    }
 }
 
 impl ConfigSource for CfgSource {
     fn get_config<T: IsConfig>(&self, key: impl AsRef<str>) -> crate::Result<T> {
-        Ok(self.conf.get::<T>(key.as_ref())?)
+        let key = key.as_ref();
+        self.read(key, |conf| conf.get::<T>(key))
     }
 
     fn get_config_or<T: IsConfig>(&self, key: impl AsRef<str>, default: T) -> crate::Result<T> {
-        Ok(self.conf.get_or::<T>(key.as_ref(), default)?)
+        let key = key.as_ref();
+        self.read(key, |conf| conf.get_or::<T>(key, default))
+    }
+}
+
+/// A config value that could not be injected into a bean field.
+#[derive(Debug)]
+pub struct ConfigIssue {
+    pub bean: &'static str,
+    pub field: &'static str,
+    pub key: String,
+    pub ty: &'static str,
+    pub env: String,
+    pub cause: crate::error::Error,
+}
+
+impl ConfigIssue {
+    /// Wraps a failed lookup, turning a plain "not found" into [`Error::MissingConfig`].
+    ///
+    /// [`Error::MissingConfig`]: crate::error::Error::MissingConfig
+    pub fn new<T>(
+        bean: &'static str,
+        field: &'static str,
+        key: &str,
+        env: String,
+        cause: crate::error::Error,
+    ) -> Self {
+        use crate::error::Error;
+
+        let cause = match cause {
+            Error::ConfigError(cfg_rs::ConfigError::ConfigNotFound(_)) => Error::MissingConfig {
+                bean,
+                field,
+                key: key.to_string(),
+            },
+            cause => cause,
+        };
+        Self {
+            bean,
+            field,
+            key: key.to_string(),
+            ty: std::any::type_name::<T>(),
+            env,
+            cause,
+        }
+    }
+}
+
+impl Display for ConfigIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use crate::error::Error;
+
+        match (&self.cause, self.env.as_str()) {
+            (Error::MissingEnv { .. }, _) => {
+                write!(f, "{} (expected `{}`)", self.cause, self.ty)
+            }
+            (Error::MissingConfig { .. }, "") => {
+                write!(f, "{} (expected `{}`)", self.cause, self.ty)
+            }
+            (Error::MissingConfig { .. }, env) => write!(
+                f,
+                "{} (expected `{}`, or set env `{env}`)",
+                self.cause, self.ty
+            ),
+            (cause, _) if self.key.is_empty() => write!(
+                f,
+                "bean `{}` field `{}` as `{}`: {}",
+                self.bean, self.field, self.ty, cause
+            ),
+            (cause, "") => write!(
+                f,
+                "bean `{}` field `{}`: key `{}` as `{}`: {}",
+                self.bean, self.field, self.key, self.ty, cause
+            ),
+            (cause, env) => write!(
+                f,
+                "bean `{}` field `{}`: key `{}` as `{}` (env `{env}`): {}",
+                self.bean, self.field, self.key, self.ty, cause
+            ),
+        }
+    }
+}
+
+/// Every [`ConfigIssue`] found while initializing the beans, with the keys
+/// rejected by [`UnknownKeys::Deny`] alongside them.
+#[derive(Debug, Default)]
+pub struct ConfigIssues {
+    issues: Vec<ConfigIssue>,
+    unknown: Vec<UnknownKey>,
+}
+
+impl ConfigIssues {
+    pub fn push(&mut self, issue: ConfigIssue) {
+        self.issues.push(issue);
+    }
+
+    /// The unknown keys found along with the issues.
+    pub fn unknown_keys(&self) -> &[UnknownKey] {
+        &self.unknown
+    }
+
+    pub(crate) fn set_unknown_keys(&mut self, keys: Vec<UnknownKey>) {
+        self.unknown = keys;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.issues.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ConfigIssue> {
+        self.issues.iter()
+    }
+}
+
+impl Display for ConfigIssues {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} config error(s)",
+            self.issues.len() + self.unknown.len()
+        )?;
+        for issue in &self.issues {
+            write!(f, "\n  - {issue}")?;
+        }
+        for key in &self.unknown {
+            write!(f, "\n  - {key}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn env_var_name() {
+        assert_eq!(env_var("APP", "bbb.name"), "APP_BBB_NAME");
+        assert_eq!(env_var("APP", "web.hosts[0].addr"), "APP_WEB_HOSTS_0_ADDR");
+    }
+
+    #[test]
+    fn issues_display() {
+        let mut issues = ConfigIssues::default();
+        issues.push(ConfigIssue::new::<String>(
+            "B",
+            "name",
+            "bbb.name",
+            env_var("APP", "bbb.name"),
+            cfg_rs::ConfigError::ConfigNotFound("bbb.name".to_string()).into(),
+        ));
+        issues.push(ConfigIssue::new::<u16>(
+            "Web",
+            "port",
+            "web.port",
+            env_var("APP", "web.port"),
+            cfg_rs::ConfigError::ConfigParseError("web.port".to_string(), "x".to_string()).into(),
+        ));
+        assert_eq!(
+            issues.to_string(),
+            "2 config error(s)\n  - bean `B` field `name`: missing config key `bbb.name` \
+             (expected `alloc::string::String`, or set env `APP_BBB_NAME`)\n  - bean `Web` field \
+             `port`: key `web.port` as `u16` (env `APP_WEB_PORT`): config error: \
+             ConfigParseError(\"web.port\", \"x\")"
+        );
+    }
+
+    #[test]
+    fn env_values() {
+        let port = Some("8080".to_string());
+        assert_eq!(
+            parse_value::<u16>("IOC_ENV_VALUE_TEST_PORT", port).unwrap(),
+            8080
+        );
+        assert_eq!(
+            parse_value::<Option<u16>>("IOC_ENV_VALUE_TEST_UNSET", None).unwrap(),
+            None
+        );
+
+        let cause = parse_value::<u16>("IOC_ENV_VALUE_TEST_UNSET", None).unwrap_err();
+        assert!(matches!(
+            cause,
+            crate::error::Error::ConfigError(cfg_rs::ConfigError::ConfigNotFound(_))
+        ));
+        let issue = ConfigIssue::new::<u16>(
+            "Web",
+            "port",
+            "IOC_ENV_VALUE_TEST_UNSET",
+            "IOC_ENV_VALUE_TEST_UNSET".to_string(),
+            crate::error::Error::MissingEnv {
+                bean: "Web",
+                field: "port",
+                name: "IOC_ENV_VALUE_TEST_UNSET".to_string(),
+            },
+        );
+        assert_eq!(
+            issue.to_string(),
+            "bean `Web` field `port`: env variable `IOC_ENV_VALUE_TEST_UNSET` is not set \
+             (expected `u16`)"
+        );
+    }
+
+    #[test]
+    fn env_values_are_verbatim() {
+        for raw in ["${HOME}", "a}b", "c:\\data", "$"] {
+            assert_eq!(
+                parse_value::<String>("TEST", Some(raw.to_string())).unwrap(),
+                raw
+            );
+        }
+    }
+
+    #[test]
+    fn profile_layers() {
+        let dir = TempDir::new("profile");
+        dir.write(
+            "app.toml",
+            "a = 'base'\nb = 'base'\nc = 'base'\nd = 'base'\n",
+        );
+        dir.write("app-dev.toml", "b = 'dev'\nc = 'dev'\nd = 'dev'\n");
+        dir.write("app.local.tml", "c = 'local'\nd = 'local'\n");
+
+        let dir = dir.display().to_string();
+        let open = |profile, vars: &[(&str, &str)]| {
+            let vars: Vec<_> = vars
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect();
+            let params = CfgParams {
+                dir: &dir,
+                prefix_env: "IOC_PROFILE_TEST",
+                profile,
+                args: Some(&[]),
+                vars: Some(&vars),
+                ..CfgParams::default()
+            };
+            CfgSource::new(params.sources()).unwrap()
+        };
+        let values = |source: &CfgSource| {
+            ["a", "b", "c", "d"].map(|key| source.get_config::<String>(key).unwrap())
+        };
+
+        let env = [("IOC_PROFILE_TEST_D", "env")];
+        assert_eq!(
+            values(&open(Some("dev"), &env)),
+            ["base", "dev", "local", "env"]
+        );
+        assert_eq!(values(&open(None, &env)), ["base", "base", "local", "env"]);
+
+        let env = [
+            ("IOC_PROFILE_TEST_D", "env"),
+            ("IOC_PROFILE_TEST_PROFILE", "dev"),
+        ];
+        assert_eq!(values(&open(None, &env)), ["base", "dev", "local", "env"]);
+    }
+
+    #[test]
+    fn dotenv_layers() {
+        let dir = TempDir::new("dotenv");
+        dir.write("app.toml", "a = 'base'\nb = 'base'\nc = 'base'\n");
+        dir.write(
+            ".env",
+            "IOC_DOTENV_TEST_B=dotenv\nIOC_DOTENV_TEST_C=dotenv\n",
+        );
+        dir.write(".env.dev", "IOC_DOTENV_TEST_C=dev\n");
+        let vars = [("IOC_DOTENV_TEST_A".to_string(), "env".to_string())];
+
+        let dir = dir.display().to_string();
+        let open = |dotenv| {
+            let params = CfgParams {
+                dir: &dir,
+                prefix_env: "IOC_DOTENV_TEST",
+                profile: Some("dev"),
+                args: Some(&[]),
+                vars: Some(&vars),
+                dotenv,
+                ..CfgParams::default()
+            };
+            let source = CfgSource::new(params.sources()).unwrap();
+            ["a", "b", "c"].map(|key| source.get_config::<String>(key).unwrap())
+        };
+
+        assert_eq!(open(true), ["env", "dotenv", "dev"]);
+        assert_eq!(open(false), ["env", "base", "base"]);
+        assert!(std::env::var("IOC_DOTENV_TEST_B").is_err());
+    }
+
+    #[test]
+    fn explicit_config_file() {
+        let dir = TempDir::new("config-file");
+        dir.write("prod.toml", "a = 'toml'\n");
+        dir.write("prod.tml", "a = 'tml'\n");
+
+        let open = |file: &str| {
+            let args = ["--config".to_string(), dir.join(file).display().to_string()];
+            let params = CfgParams {
+                prefix_env: "IOC_CONFIG_FILE_TEST",
+                args: Some(&args),
+                vars: Some(&[]),
+                ..CfgParams::default()
+            };
+            CfgSource::new(params.sources())
+        };
+
+        let source = open("prod.tml").unwrap();
+        assert_eq!(source.get_config::<String>("a").unwrap(), "tml");
+        assert!(matches!(
+            open("missing.toml"),
+            Err(crate::error::Error::ConfigError(
+                cfg_rs::ConfigError::ConfigFileNotExists(_)
+            ))
+        ));
+    }
+
+    #[test]
+    fn app_env_params() {
+        let dir = TempDir::new("app-env");
+        dir.write("svc.toml", "a = 'svc'\nb = 'svc'\n");
+        dir.write("svc-blue.toml", "b = 'blue'\n");
+
+        let vars = [
+            ("IOC_APP_ENV_TEST_APP_NAME", "svc"),
+            ("IOC_APP_ENV_TEST_APP_DIR", &dir.display().to_string()),
+            ("IOC_APP_ENV_TEST_APP_PROFILE", "blue"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+        let params = CfgParams {
+            prefix_env: "IOC_APP_ENV_TEST",
+            args: Some(&[]),
+            vars: Some(&vars),
+            ..CfgParams::default()
+        };
+        let source = CfgSource::new(params.sources()).unwrap();
+        let values = ["a", "b", "app.name", "app.dir", "app.profile"]
+            .map(|key| source.get_config::<String>(key).unwrap());
+        assert_eq!(
+            values,
+            ["svc", "blue", "svc", &dir.display().to_string(), "blue"]
+        );
+    }
+
+    #[test]
+    fn command_line_params() {
+        let dir = TempDir::new("args");
+        dir.write("prod.toml", "a = 'prod'\nb = 'prod'\nc = 'prod'\n");
+        dir.write("prod-blue.toml", "b = 'blue'\nc = 'blue'\n");
+
+        let args = [
+            "--config".to_string(),
+            dir.join("prod.toml").display().to_string(),
+            "--profile=blue".to_string(),
+            "-D".to_string(),
+            "c=cli".to_string(),
+        ];
+        let params = CfgParams {
+            prefix_env: "IOC_ARGS_TEST",
+            args: Some(&args),
+            ..CfgParams::default()
+        };
+        let source = CfgSource::new(params.sources()).unwrap();
+        let values = ["a", "b", "c"].map(|key| source.get_config::<String>(key).unwrap());
+        assert_eq!(values, ["prod", "blue", "cli"]);
     }
 }
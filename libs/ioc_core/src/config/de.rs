@@ -0,0 +1,238 @@
+//! Reading `serde::Deserialize` types from config, enabled by the `serde` feature.
+
+use cfg_rs::{ConfigContext, ConfigError, ConfigValue, FromConfig};
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{self, DeserializeOwned, Deserializer, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+
+/// Reads `T` through its `Deserialize` impl instead of `FromConfig`.
+///
+/// Used by `#[rivete(config, serde)]` fields, or directly with
+/// `ctx.get_config::<Serde<T>>(key)`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Serde<T>(pub T);
+
+impl<T> Serde<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: DeserializeOwned> FromConfig for Serde<T> {
+    fn from_config(
+        context: &mut ConfigContext<'_>,
+        value: Option<ConfigValue<'_>>,
+    ) -> Result<Self, ConfigError> {
+        let tree = Tree::from_config(context, value)?;
+        T::deserialize(tree)
+            .map(Serde)
+            .map_err(|err| ConfigError::ConfigParseError(context.current_key(), err.0))
+    }
+}
+
+/// The config subtree below a key, collected before handing it to serde.
+#[derive(Debug)]
+enum Tree {
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Map(HashMap<String, Tree>),
+    List(Vec<Tree>),
+}
+
+impl FromConfig for Tree {
+    fn from_config(
+        context: &mut ConfigContext<'_>,
+        value: Option<ConfigValue<'_>>,
+    ) -> Result<Self, ConfigError> {
+        match value {
+            Some(ConfigValue::Int(v)) => Ok(Tree::Int(v)),
+            Some(ConfigValue::Float(v)) => Ok(Tree::Float(v)),
+            Some(ConfigValue::Bool(v)) => Ok(Tree::Bool(v)),
+            // Strings may hold `${...}` placeholders, which cfg-rs resolves.
+            Some(value) => String::from_config(context, Some(value)).map(Tree::Str),
+            None => {
+                let map = HashMap::<String, Tree>::from_config(context, None)?;
+                if !map.is_empty() {
+                    return Ok(Tree::Map(map));
+                }
+                let list = Vec::<Tree>::from_config(context, None)?;
+                if !list.is_empty() {
+                    return Ok(Tree::List(list));
+                }
+                Err(ConfigError::ConfigNotFound(context.current_key()))
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Error(String);
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+
+impl IntoDeserializer<'_, Error> for Tree {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Env variables and most file formats only carry strings, so scalars are
+/// parsed from strings on demand, like `FromConfig` does.
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident),* $(,)?) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self {
+                Tree::Str(s) => match s.trim().parse() {
+                    Ok(v) => visitor.$visit(v),
+                    Err(_) => Err(de::Error::invalid_value(de::Unexpected::Str(&s), &visitor)),
+                },
+                tree => tree.deserialize_any(visitor),
+            }
+        }
+    )*};
+}
+
+impl<'de> Deserializer<'de> for Tree {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Tree::Str(v) => visitor.visit_string(v),
+            Tree::Int(v) => visitor.visit_i64(v),
+            Tree::Float(v) => visitor.visit_f64(v),
+            Tree::Bool(v) => visitor.visit_bool(v),
+            Tree::Map(map) => {
+                // Sorted so errors and visit order do not depend on hashing.
+                let map: BTreeMap<_, _> = map.into_iter().collect();
+                visitor.visit_map(MapDeserializer::new(map.into_iter()))
+            }
+            Tree::List(list) => visitor.visit_seq(SeqDeserializer::new(list.into_iter())),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self {
+            Tree::Str(variant) => visitor.visit_enum(variant.into_deserializer()),
+            tree => tree.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct seq tuple tuple_struct
+        map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfg_rs::Configuration;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Level {
+        Info,
+        Debug,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Log {
+        level: Level,
+        targets: Vec<String>,
+        #[serde(default)]
+        color: bool,
+        buffer: Option<u32>,
+    }
+
+    fn config() -> Configuration {
+        Configuration::new()
+            .register_kv("test")
+            .set("log.level", "debug")
+            .set("log.targets[0]", "stdout")
+            .set("log.targets[1]", "file")
+            .set("log.buffer", "64")
+            .finish()
+            .unwrap()
+    }
+
+    #[test]
+    fn deserialize_subtree() {
+        let Serde(log) = config().get::<Serde<Log>>("log").unwrap();
+        assert_eq!(
+            log,
+            Log {
+                level: Level::Debug,
+                targets: vec!["stdout".to_string(), "file".to_string()],
+                color: false,
+                buffer: Some(64),
+            }
+        );
+        assert!(
+            config()
+                .get::<Option<Serde<Log>>>("missing")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn report_key_on_error() {
+        let err = config().get::<Serde<u32>>("log.level").unwrap_err();
+        assert!(
+            matches!(&err, ConfigError::ConfigParseError(key, _) if key == "log.level"),
+            "{err:?}"
+        );
+    }
+}
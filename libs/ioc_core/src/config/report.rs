@@ -0,0 +1,260 @@
+//! The effective value of every config key read by a bean, and where it came from.
+
+use super::{CfgSource, secret};
+use crate::schema::{ConfigDefault, ConfigEntry, toml_string};
+use cfg_rs::{ConfigContext, ConfigError, ConfigValue, Configuration, FromConfig};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::PoisonError;
+
+/// Built by [`Ctx::config_report`](crate::Ctx::config_report); its `Display`
+/// prints one line per key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigReport {
+    pub entries: Vec<ReportEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportEntry {
+    pub key: &'static str,
+    /// The `Bean.field`s reading the key.
+    pub users: Vec<String>,
+    /// The value as it would be written in TOML, with `***` for each
+    /// [`Secret`](super::Secret) in it.
+    pub value: Option<String>,
+    pub origin: Origin,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Origin {
+    /// The name of the provider that supplied the value, e.g. `env:APP_*`.
+    Source(String),
+    /// The `default = ...` of the field.
+    Default,
+    Unset,
+}
+
+impl Display for Origin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Origin::Source(name) => f.write_str(name),
+            Origin::Default => f.write_str("default"),
+            Origin::Unset => f.write_str("unset"),
+        }
+    }
+}
+
+impl Display for ConfigReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for entry in &self.entries {
+            let value = entry.value.as_deref().unwrap_or("-");
+            writeln!(
+                f,
+                "{} = {value}  # {} ({})",
+                entry.key,
+                entry.origin,
+                entry.users.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl CfgSource {
+    pub(crate) fn report(&self, entries: &[&ConfigEntry]) -> ConfigReport {
+        let mut secrets = self
+            .secrets
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let mut report: Vec<ReportEntry> = Vec::new();
+        for entry in entries {
+            let user = format!("{}.{}", entry.bean, entry.field);
+            if let Some(last) = report.last_mut().filter(|last| last.key == entry.key) {
+                last.users.push(user);
+                continue;
+            }
+
+            let origin = self
+                .layers
+                .iter()
+                .find(|(_, conf)| provides(conf, entry.key))
+                .map(|(name, _)| Origin::Source(name.to_string()));
+            let (value, origin) = match (origin, entry.default) {
+                (Some(origin), _) => {
+                    let rendered = secret::with_secret_keys(&mut secrets, || {
+                        self.conf.get::<Rendered>(entry.key)
+                    });
+                    let value = match rendered {
+                        Ok(Rendered(value)) => value,
+                        Err(err) => format!("<{err:?}>"),
+                    };
+                    (Some(value), origin)
+                }
+                (None, Some(ConfigDefault::Literal(value))) => {
+                    (Some(value.to_string()), Origin::Default)
+                }
+                (None, Some(ConfigDefault::Expr(expr))) => {
+                    (Some(format!("`{expr}`")), Origin::Default)
+                }
+                (None, None) => (None, Origin::Unset),
+            };
+            // a `Secret` field may not have been read, e.g. its bean failed
+            let value = match entry.is_secret() {
+                true => value.map(|_| "***".to_string()),
+                false => value,
+            };
+            report.push(ReportEntry {
+                key: entry.key,
+                users: vec![user],
+                value,
+                origin,
+            });
+        }
+        ConfigReport { entries: report }
+    }
+}
+
+/// Whether `key`, or a key below it, is set; a value whose placeholders only
+/// resolve against other providers still counts.
+pub(super) fn provides(conf: &Configuration, key: &str) -> bool {
+    !matches!(
+        conf.get::<Rendered>(key),
+        Err(ConfigError::ConfigNotFound(_))
+    )
+}
+
+/// A config value or subtree written as TOML, `***` at and below the keys
+/// read as a [`Secret`](super::Secret).
+pub(crate) struct Rendered(pub(crate) String);
+
+impl FromConfig for Rendered {
+    fn from_config(
+        context: &mut ConfigContext<'_>,
+        value: Option<ConfigValue<'_>>,
+    ) -> Result<Self, ConfigError> {
+        let rendered = Self::render(context, value)?;
+        match secret::is_secret(&context.current_key()) {
+            true => Ok(Rendered("***".to_string())),
+            false => Ok(rendered),
+        }
+    }
+}
+
+impl Rendered {
+    fn render(
+        context: &mut ConfigContext<'_>,
+        value: Option<ConfigValue<'_>>,
+    ) -> Result<Self, ConfigError> {
+        match value {
+            Some(ConfigValue::Int(v)) => Ok(Rendered(v.to_string())),
+            Some(ConfigValue::Float(v)) => Ok(Rendered(v.to_string())),
+            Some(ConfigValue::Bool(v)) => Ok(Rendered(v.to_string())),
+            Some(value) => {
+                String::from_config(context, Some(value)).map(|s| Rendered(toml_string(&s)))
+            }
+            None => {
+                let map = HashMap::<String, Rendered>::from_config(context, None)?;
+                if !map.is_empty() {
+                    let mut pairs: Vec<_> = map
+                        .into_iter()
+                        .map(|(key, Rendered(value))| format!("{key} = {value}"))
+                        .collect();
+                    pairs.sort();
+                    return Ok(Rendered(format!("{{ {} }}", pairs.join(", "))));
+                }
+                let list = Vec::<Rendered>::from_config(context, None)?;
+                if !list.is_empty() {
+                    let items: Vec<_> = list.into_iter().map(|Rendered(value)| value).collect();
+                    return Ok(Rendered(format!("[{}]", items.join(", "))));
+                }
+                Err(ConfigError::ConfigNotFound(context.current_key()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigSource, Secret};
+    use crate::source::ConfigSources;
+
+    fn entry(
+        field: &'static str,
+        key: &'static str,
+        default: Option<ConfigDefault>,
+    ) -> ConfigEntry {
+        ConfigEntry {
+            bean: "Web",
+            field,
+            key,
+            ty: std::any::type_name::<String>,
+            secret: || false,
+            default,
+        }
+    }
+
+    #[test]
+    fn provenance() {
+        let entries = [
+            entry("host", "web.host", None),
+            entry("origins", "web.origins", None),
+            entry("port", "web.port", Some(ConfigDefault::Literal("8080"))),
+            entry("proxy", "web.proxy", None),
+            ConfigEntry {
+                secret: || true,
+                ..entry("token", "web.token", None)
+            },
+            entry("url", "web.url", None),
+            entry("address", "web.url", None),
+        ];
+        let sources = ConfigSources::new()
+            .memory("override", [("web.host", "example.com")])
+            .toml(
+                "app.toml",
+                "[web]\nhost = 'localhost'\norigins = ['a', 'b']\ntoken = 'hunter2'\nurl = 'http://${web.host}'\n",
+            );
+        let config = CfgSource::new(sources).unwrap();
+        let report = config.report(&entries.iter().collect::<Vec<_>>());
+
+        assert_eq!(
+            report.to_string(),
+            concat!(
+                "web.host = \"example.com\"  # override (Web.host)\n",
+                "web.origins = [\"a\", \"b\"]  # app.toml (Web.origins)\n",
+                "web.port = 8080  # default (Web.port)\n",
+                "web.proxy = -  # unset (Web.proxy)\n",
+                "web.token = ***  # app.toml (Web.token)\n",
+                "web.url = \"http://example.com\"  # app.toml (Web.url, Web.address)\n",
+            )
+        );
+    }
+
+    #[test]
+    fn nested_secrets() {
+        let entries = [
+            entry("tls", "web.tls", None),
+            entry("keys", "web.keys", None),
+        ];
+        let sources = ConfigSources::new().toml(
+            "app.toml",
+            "[web.tls]\ncert = 'cert.pem'\nkey = 'hunter2'\n[web.keys]\nprimary = 'hunter3'\n",
+        );
+        let config = CfgSource::new(sources).unwrap();
+        // as read by the `FromConfig` of the fields
+        config.get_config::<Secret<String>>("web.tls.key").unwrap();
+        config
+            .get_config::<Secret<HashMap<String, String>>>("web.keys")
+            .unwrap();
+        let report = config.report(&entries.iter().collect::<Vec<_>>());
+
+        assert_eq!(
+            report.to_string(),
+            concat!(
+                "web.tls = { cert = \"cert.pem\", key = *** }  # app.toml (Web.tls)\n",
+                "web.keys = ***  # app.toml (Web.keys)\n",
+            )
+        );
+    }
+}
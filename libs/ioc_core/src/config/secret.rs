@@ -0,0 +1,217 @@
+//! Config values that must not show up in logs or dumps.
+
+use cfg_rs::{ConfigContext, ConfigError, ConfigValue, FromConfig};
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::fmt::{Debug, Display, Formatter};
+use std::marker::PhantomData;
+use std::path::PathBuf;
+
+const FILE_PREFIX: &str = "file:";
+
+/// A config value whose `Debug` and `Display` print `***`.
+///
+/// A value written as `"file:/run/secrets/db"` is replaced by the content of
+/// that file, without its trailing newline, before `T` is parsed from it. A
+/// relative path is resolved against the config dir, like a
+/// [`ConfigPath`](super::ConfigPath). Only `Secret` fields do this: a plain
+/// `String` field set to `"file:..."` reads that text as it is.
+#[derive(Clone, Copy, Default)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// The actual value; keep it out of anything that gets printed.
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> Debug for Secret<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Secret(***)")
+    }
+}
+
+impl<T> Display for Secret<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("***")
+    }
+}
+
+/// Tells the derives whether a field type is a [`Secret`], or an `Option` of
+/// one, for [`ConfigEntry::is_secret`](crate::schema::ConfigEntry::is_secret):
+/// `(&SecretProbe::<T>::NEW).is_secret()` picks [`IsSecret`] when `T` is one,
+/// and falls back to [`NotSecret`] otherwise.
+#[doc(hidden)]
+pub struct SecretProbe<T: ?Sized>(PhantomData<T>);
+
+impl<T: ?Sized> SecretProbe<T> {
+    pub const NEW: Self = Self(PhantomData);
+}
+
+#[doc(hidden)]
+pub trait IsSecret {
+    fn is_secret(&self) -> bool {
+        true
+    }
+}
+
+impl<T> IsSecret for SecretProbe<Secret<T>> {}
+
+impl<T> IsSecret for SecretProbe<Option<Secret<T>>> {}
+
+#[doc(hidden)]
+pub trait NotSecret {
+    fn is_secret(&self) -> bool {
+        false
+    }
+}
+
+impl<T: ?Sized> NotSecret for &SecretProbe<T> {}
+
+thread_local! {
+    /// The keys read as a `Secret`, see [`with_secret_keys`].
+    static SECRET_KEYS: RefCell<BTreeSet<String>> = const { RefCell::new(BTreeSet::new()) };
+}
+
+/// Runs `read` with `keys` as the keys known to hold a [`Secret`]: the ones
+/// it reads as a `Secret` are added, and values at or below any of them are
+/// rendered as `***`.
+pub(crate) fn with_secret_keys<R>(keys: &mut BTreeSet<String>, read: impl FnOnce() -> R) -> R {
+    let previous = SECRET_KEYS.replace(std::mem::take(keys));
+    let result = read();
+    *keys = SECRET_KEYS.replace(previous);
+    result
+}
+
+/// Whether `key` is, or is below, a key known to hold a [`Secret`].
+pub(crate) fn is_secret(key: &str) -> bool {
+    SECRET_KEYS.with_borrow(|keys| {
+        keys.iter().any(|secret| {
+            key.strip_prefix(secret.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(['.', '[']))
+        })
+    })
+}
+
+fn read_file(context: &mut ConfigContext<'_>, path: &str) -> Result<String, ConfigError> {
+    let path = super::types::resolve_path(context, PathBuf::from(path))?;
+    match std::fs::read_to_string(&path) {
+        Ok(content) => Ok(content.trim_end_matches(['\n', '\r']).to_string()),
+        Err(err) => Err(context.parse_error(&format!("{FILE_PREFIX}{} ({err})", path.display()))),
+    }
+}
+
+/// Drops the value from the errors of `T`, which may quote it, keeping the key.
+fn redact(context: &ConfigContext<'_>, err: ConfigError) -> ConfigError {
+    match err {
+        ConfigError::ConfigParseError(key, _) => {
+            ConfigError::ConfigParseError(key, "***".to_string())
+        }
+        ConfigError::ConfigCause(_) => context.parse_error("***"),
+        err => err,
+    }
+}
+
+impl<T: FromConfig> FromConfig for Secret<T> {
+    fn from_config(
+        context: &mut ConfigContext<'_>,
+        value: Option<ConfigValue<'_>>,
+    ) -> Result<Self, ConfigError> {
+        SECRET_KEYS.with_borrow_mut(|keys| keys.insert(context.current_key()));
+        let path = match &value {
+            Some(ConfigValue::StrRef(s)) => s.strip_prefix(FILE_PREFIX),
+            Some(ConfigValue::Str(s)) => s.strip_prefix(FILE_PREFIX),
+            _ => None,
+        };
+        let value = match path {
+            Some(path) => Some(ConfigValue::Str(read_file(context, path.trim())?)),
+            None => value,
+        };
+        T::from_config(context, value)
+            .map(Self)
+            .map_err(|err| redact(context, err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use cfg_rs::Configuration;
+
+    #[test]
+    fn redacted_and_read_from_files() {
+        let dir = TempDir::new("secret");
+        dir.write("password", "hunter2\n");
+        let path = dir.join("password");
+
+        let config = Configuration::new()
+            .register_kv("test")
+            .set("db.password", format!("file:{}", path.display()))
+            .set("db.token", "plain")
+            .set("db.pin", 1234)
+            .set("db.port", "hunter2")
+            .set("db.missing", "file:/nonexistent/ioc-secret")
+            .finish()
+            .unwrap();
+
+        let password: Secret<String> = config.get("db.password").unwrap();
+        assert_eq!(password.expose(), "hunter2");
+        assert_eq!(format!("{password:?} {password}"), "Secret(***) ***");
+
+        let token: Secret<String> = config.get("db.token").unwrap();
+        assert_eq!(token.into_inner(), "plain");
+        let pin: Secret<u16> = config.get("db.pin").unwrap();
+        assert_eq!(*pin.expose(), 1234);
+        assert!(config.get::<Secret<String>>("db.missing").is_err());
+        let err = config.get::<Secret<u16>>("db.port").unwrap_err();
+        assert!(
+            matches!(&err, ConfigError::ConfigParseError(key, value) if key == "db.port" && value == "***"),
+            "{err:?}"
+        );
+        assert!(
+            config
+                .get::<Option<Secret<String>>>("db.none")
+                .unwrap()
+                .is_none()
+        );
+
+        let config = Configuration::new()
+            .register_kv("test")
+            .set("db.password", "file:password")
+            .finish()
+            .unwrap();
+        assert!(config.get::<Secret<String>>("db.password").is_err());
+        let password = super::super::types::with_base_dir(Some(&dir), || {
+            config.get::<Secret<String>>("db.password")
+        });
+        assert_eq!(password.unwrap().expose(), "hunter2");
+    }
+
+    #[test]
+    // the borrow is what lets `NotSecret` apply to any other type
+    #[allow(clippy::needless_borrow)]
+    fn secret_types() {
+        type Password = Secret<String>;
+
+        assert!((&SecretProbe::<Password>::NEW).is_secret());
+        assert!((&SecretProbe::<Option<Secret<u16>>>::NEW).is_secret());
+        assert!(!(&SecretProbe::<String>::NEW).is_secret());
+        assert!(!(&SecretProbe::<Option<String>>::NEW).is_secret());
+    }
+}
@@ -0,0 +1,449 @@
+//! Human-readable config value types.
+//!
+//! `SocketAddr`, `IpAddr` and `PathBuf` are read by cfg-rs as they are; the
+//! types here cover what it cannot parse.
+
+use cfg_rs::{ConfigContext, ConfigError, ConfigValue, FromConfig};
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// A number as written, or a string with its `${...}` placeholders resolved.
+enum Scalar {
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+fn scalar(
+    context: &mut ConfigContext<'_>,
+    value: Option<ConfigValue<'_>>,
+) -> Result<Scalar, ConfigError> {
+    match value {
+        None => Err(ConfigError::ConfigNotFound(context.current_key())),
+        Some(ConfigValue::Int(v)) => Ok(Scalar::Int(v)),
+        Some(ConfigValue::Float(v)) => Ok(Scalar::Float(v)),
+        // a key set to `""` is present, its value just does not parse
+        Some(value) => match String::from_config(context, Some(value))? {
+            s if s.trim().is_empty() => Err(context.parse_error(&s)),
+            s => Ok(Scalar::Str(s)),
+        },
+    }
+}
+
+/// Splits `"1.5 GiB"` into `("1.5", "GiB")`.
+fn split_unit(s: &str) -> (&str, &str) {
+    let s = s.trim();
+    let end = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    (&s[..end], s[end..].trim_start())
+}
+
+/// A [`Duration`] written as a sum of the durations cfg-rs reads, e.g.
+/// `"1m30s"` or `"2d 12h"`, where a plain `Duration` takes a single one.
+///
+/// Each part is a whole number with a unit, `ns`, `us`, `ms`, `s`, `m` or `h`
+/// as parsed by cfg-rs, or `d` and `w`; a bare number counts seconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HumanDuration(pub Duration);
+
+/// Splits `"1m30s"` into `["1m", "30s"]` and `"2d 12h"` into `["2d", "12h"]`.
+fn duration_parts(s: &str) -> impl Iterator<Item = &str> {
+    s.split_whitespace().flat_map(|mut rest| {
+        std::iter::from_fn(move || {
+            let number = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let end = rest[number..]
+                .find(|c: char| c.is_ascii_digit())
+                .map_or(rest.len(), |unit| number + unit);
+            let (part, tail) = rest.split_at(end);
+            rest = tail;
+            (!part.is_empty()).then_some(part)
+        })
+    })
+}
+
+impl FromConfig for HumanDuration {
+    fn from_config(
+        context: &mut ConfigContext<'_>,
+        value: Option<ConfigValue<'_>>,
+    ) -> Result<Self, ConfigError> {
+        let s = match scalar(context, value)? {
+            Scalar::Str(s) => s,
+            Scalar::Int(seconds) if seconds >= 0 => {
+                let seconds = ConfigValue::Int(seconds);
+                return Duration::from_config(context, Some(seconds)).map(Self);
+            }
+            Scalar::Float(seconds) if Duration::try_from_secs_f64(seconds).is_ok() => {
+                let seconds = ConfigValue::Float(seconds);
+                return Duration::from_config(context, Some(seconds)).map(Self);
+            }
+            Scalar::Int(seconds) => return Err(context.parse_error(&seconds.to_string())),
+            Scalar::Float(seconds) => return Err(context.parse_error(&seconds.to_string())),
+        };
+
+        let mut total = Duration::ZERO;
+        for part in duration_parts(&s) {
+            // cfg-rs reads `"m"` alone as zero minutes
+            if !part.starts_with(|c: char| c.is_ascii_digit()) {
+                return Err(context.parse_error(&s));
+            }
+            let (part, hours) = match part.strip_suffix(['d', 'w']) {
+                Some(number) if part.ends_with('d') => (format!("{number}h"), 24),
+                Some(number) => (format!("{number}h"), 24 * 7),
+                None => (part.to_string(), 1),
+            };
+            let duration = Duration::from_config(context, Some(ConfigValue::Str(part)))
+                .ok()
+                .and_then(|duration| duration.checked_mul(hours))
+                .and_then(|duration| total.checked_add(duration));
+            match duration {
+                Some(duration) => total = duration,
+                None => return Err(context.parse_error(&s)),
+            }
+        }
+        Ok(Self(total))
+    }
+}
+
+impl Deref for HumanDuration {
+    type Target = Duration;
+
+    fn deref(&self) -> &Duration {
+        &self.0
+    }
+}
+
+impl From<HumanDuration> for Duration {
+    fn from(value: HumanDuration) -> Self {
+        value.0
+    }
+}
+
+/// A number of bytes written as `"512"`, `"10MB"` or `"1.5 GiB"`.
+///
+/// `KB`, `MB`, `GB` and `TB` are powers of 1000, `KiB`, `MiB`, `GiB` and
+/// `TiB` powers of 1024; units are case insensitive and the `B` optional.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    pub fn parse(s: &str) -> Option<Self> {
+        let (number, unit) = split_unit(s);
+        let unit = unit.to_ascii_lowercase();
+        let multiplier: u64 = match unit.strip_suffix('b').unwrap_or(&unit) {
+            "" => 1,
+            "k" => 1_000,
+            "m" => 1_000_000,
+            "g" => 1_000_000_000,
+            "t" => 1_000_000_000_000,
+            "ki" => 1 << 10,
+            "mi" => 1 << 20,
+            "gi" => 1 << 30,
+            "ti" => 1 << 40,
+            _ => return None,
+        };
+        // whole numbers stay exact, above 2^53 too
+        if let Ok(number) = number.parse::<u64>() {
+            return number.checked_mul(multiplier).map(Self);
+        }
+        let bytes = number.parse::<f64>().ok()? * multiplier as f64;
+        // `u64::MAX as f64` rounds up to 2^64, which is out of range
+        (bytes.fract() == 0.0 && bytes < u64::MAX as f64).then_some(Self(bytes as u64))
+    }
+
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+}
+
+impl FromConfig for ByteSize {
+    fn from_config(
+        context: &mut ConfigContext<'_>,
+        value: Option<ConfigValue<'_>>,
+    ) -> Result<Self, ConfigError> {
+        match scalar(context, value)? {
+            Scalar::Int(bytes) if bytes >= 0 => Ok(Self(bytes as u64)),
+            Scalar::Int(bytes) => Err(context.parse_error(&bytes.to_string())),
+            Scalar::Float(bytes) => Err(context.parse_error(&bytes.to_string())),
+            Scalar::Str(s) => Self::parse(&s).ok_or_else(|| context.parse_error(&s)),
+        }
+    }
+}
+
+impl Display for ByteSize {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}B", self.0)
+    }
+}
+
+/// A path in the config, relative paths being resolved against the base dir
+/// of the sources, see [`ConfigSources::base_dir`](crate::ConfigSources::base_dir),
+/// instead of the working directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ConfigPath(pub PathBuf);
+
+thread_local! {
+    /// The base dir of the config being read, see [`with_base_dir`].
+    static BASE_DIR: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Runs `read` with `dir` as the directory [`ConfigPath`] resolves against.
+pub(crate) fn with_base_dir<R>(dir: Option<&Path>, read: impl FnOnce() -> R) -> R {
+    let previous = BASE_DIR.replace(dir.map(Path::to_path_buf));
+    let result = read();
+    BASE_DIR.set(previous);
+    result
+}
+
+/// `path` as is when absolute, else joined to the base dir of [`with_base_dir`].
+pub(crate) fn resolve_path(
+    context: &ConfigContext<'_>,
+    path: PathBuf,
+) -> Result<PathBuf, ConfigError> {
+    if path.is_absolute() {
+        return Ok(path);
+    }
+    match BASE_DIR.with_borrow(|dir| dir.as_ref().map(|dir| dir.join(&path))) {
+        Some(path) => Ok(path),
+        None => Err(ConfigError::ConfigCause(
+            format!(
+                "relative path `{}` at `{}` needs a base dir, see `ConfigSources::base_dir`",
+                path.display(),
+                context.current_key()
+            )
+            .into(),
+        )),
+    }
+}
+
+impl FromConfig for ConfigPath {
+    fn from_config(
+        context: &mut ConfigContext<'_>,
+        value: Option<ConfigValue<'_>>,
+    ) -> Result<Self, ConfigError> {
+        let Scalar::Str(path) = scalar(context, value)? else {
+            return Err(context.parse_error("path"));
+        };
+        resolve_path(context, PathBuf::from(path)).map(Self)
+    }
+}
+
+impl Deref for ConfigPath {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+/// An absolute URL such as `"https://user@example.com:8443/api?v=1"`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Url {
+    raw: String,
+    scheme: String,
+    host: String,
+    port: Option<u16>,
+    path: String,
+}
+
+impl Url {
+    pub fn parse(s: &str) -> Option<Self> {
+        let raw = s.trim();
+        let (scheme, rest) = raw.split_once("://")?;
+        let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+        if !valid_scheme {
+            return None;
+        }
+        let authority_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+        let (authority, path) = rest.split_at(authority_end);
+        let host_port = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+
+        let (host, port) = if let Some(v6) = host_port.strip_prefix('[') {
+            let (host, after) = v6.split_once(']')?;
+            match after {
+                "" => (host, None),
+                after => (host, Some(after.strip_prefix(':')?)),
+            }
+        } else {
+            match host_port.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (host_port, None),
+            }
+        };
+        let port = match port {
+            Some(port) => Some(port.parse().ok()?),
+            None => None,
+        };
+        if host.is_empty() && scheme != "file" || host.contains(char::is_whitespace) {
+            return None;
+        }
+
+        Some(Self {
+            raw: raw.to_string(),
+            scheme: scheme.to_ascii_lowercase(),
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    /// The path with the query and fragment, e.g. `/api?v=1`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl FromConfig for Url {
+    fn from_config(
+        context: &mut ConfigContext<'_>,
+        value: Option<ConfigValue<'_>>,
+    ) -> Result<Self, ConfigError> {
+        match scalar(context, value)? {
+            Scalar::Str(s) => Self::parse(&s).ok_or_else(|| context.parse_error(&s)),
+            _ => Err(context.parse_error("url")),
+        }
+    }
+}
+
+impl Display for Url {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfg_rs::Configuration;
+    use std::net::SocketAddr;
+
+    fn config() -> Configuration {
+        Configuration::new()
+            .register_kv("test")
+            .set("web.graceful_shutdown_timeout", "1m30s")
+            .set("web.idle", 15)
+            .set("web.poll", "250ms")
+            .set("web.ttl", "2d 12h")
+            .set("web.fraction", "1.5s")
+            .set("web.spaced", "1 m")
+            .set("web.soon", "soon")
+            .set("web.max_body", "10MB")
+            .set("web.buffer", "1.5 KiB")
+            .set("web.empty", "")
+            .set("web.addr", "127.0.0.1:8080")
+            .set("web.static", "static")
+            .set("web.upstream_cert", "/etc/ssl/upstream.pem")
+            .set("web.upstream", "https://user@[::1]:8443/api?v=1")
+            .finish()
+            .unwrap()
+    }
+
+    #[test]
+    fn durations() {
+        let config = config();
+        let duration = |key: &str| config.get::<HumanDuration>(key).map(|duration| duration.0);
+        assert_eq!(
+            duration("web.graceful_shutdown_timeout").unwrap(),
+            Duration::from_secs(90)
+        );
+        assert_eq!(duration("web.idle").unwrap(), Duration::from_secs(15));
+        assert_eq!(duration("web.poll").unwrap(), Duration::from_millis(250));
+        assert_eq!(duration("web.ttl").unwrap(), Duration::from_secs(216000));
+        assert!(duration("web.fraction").is_err());
+        assert!(duration("web.spaced").is_err());
+        assert!(duration("web.soon").is_err());
+        assert!(duration("web.max_body").is_err());
+        assert!(matches!(
+            duration("web.empty"),
+            Err(ConfigError::ConfigParseError(key, _)) if key == "web.empty"
+        ));
+    }
+
+    #[test]
+    fn byte_sizes() {
+        let config = config();
+        assert_eq!(
+            config.get::<ByteSize>("web.max_body").unwrap(),
+            ByteSize(10_000_000)
+        );
+        assert_eq!(
+            config.get::<ByteSize>("web.buffer").unwrap(),
+            ByteSize(1536)
+        );
+        assert_eq!(ByteSize::parse("512").unwrap(), ByteSize(512));
+        assert_eq!(ByteSize::parse("4gib").unwrap(), ByteSize(4 << 30));
+        assert!(ByteSize::parse("0.5B").is_none());
+        assert_eq!(
+            ByteSize::parse("18446744073709551615").unwrap(),
+            ByteSize(u64::MAX)
+        );
+        assert_eq!(
+            ByteSize::parse("9007199254740993").unwrap(),
+            ByteSize(9_007_199_254_740_993)
+        );
+        assert!(ByteSize::parse("18446744073709551616").is_none());
+        assert!(ByteSize::parse("16777216 TiB").is_none());
+        assert!(ByteSize::parse("16777216.0 TiB").is_none());
+        assert!(ByteSize::parse("").is_none());
+        assert!(ByteSize::parse("10 parsecs").is_none());
+    }
+
+    #[test]
+    fn addresses_and_paths() {
+        let config = config();
+        let addr: SocketAddr = config.get("web.addr").unwrap();
+        assert_eq!(addr.port(), 8080);
+
+        let path = with_base_dir(Some(Path::new("/etc/app")), || {
+            config.get::<ConfigPath>("web.static")
+        });
+        assert_eq!(path.unwrap().0, Path::new("/etc/app/static"));
+        let path: ConfigPath = config.get("web.upstream_cert").unwrap();
+        assert_eq!(path.0, Path::new("/etc/ssl/upstream.pem"));
+        assert!(config.get::<ConfigPath>("web.static").is_err());
+    }
+
+    #[test]
+    fn urls() {
+        let url: Url = config().get("web.upstream").unwrap();
+        assert_eq!(url.scheme(), "https");
+        assert_eq!(url.host(), "::1");
+        assert_eq!(url.port(), Some(8443));
+        assert_eq!(url.path(), "/api?v=1");
+        assert_eq!(url.to_string(), "https://user@[::1]:8443/api?v=1");
+
+        let url = Url::parse("http://example.com").unwrap();
+        assert_eq!(
+            (url.host(), url.port(), url.path()),
+            ("example.com", None, "")
+        );
+        assert!(Url::parse("file:///tmp/x").is_some());
+        assert!(Url::parse("example.com").is_none());
+        assert!(Url::parse("http://example.com:http").is_none());
+        assert!(Url::parse("http:///path").is_none());
+    }
+}
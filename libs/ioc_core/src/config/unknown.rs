@@ -0,0 +1,214 @@
+//! Config keys that no bean reads, usually typos.
+
+use super::CfgSource;
+use super::report::provides;
+use crate::link::WATCH_KEYS;
+use crate::schema::ConfigEntry;
+use cfg_rs::{ConfigContext, ConfigError, ConfigValue, FromConfig};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::PoisonError;
+
+/// What to do with config keys that no linked bean reads.
+///
+/// A key is known when a config field reads it, `#[rivete(watch = ...)]`
+/// watches it, or it is read through the context while the beans are built,
+/// e.g. by a `value = ...` expression; in each case the keys below it are
+/// known too. The check runs once the beans are built, so a key that is only
+/// read later counts as unknown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnknownKeys {
+    Ignore,
+    /// Logs a warning per key.
+    #[default]
+    Warn,
+    /// Fails with [`Error::UnknownConfigKeys`](crate::error::Error::UnknownConfigKeys).
+    Deny,
+}
+
+/// Keys under these are set by the framework or hold its own settings.
+const RESERVED: [&str; 2] = ["app", "profile"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownKey {
+    pub key: String,
+    /// The provider the key was found in.
+    pub source: String,
+    /// The closest key read by a bean, if there is one close enough.
+    pub suggestion: Option<&'static str>,
+}
+
+impl Display for UnknownKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown config key `{}` in {}", self.key, self.source)?;
+        if let Some(suggestion) = self.suggestion {
+            write!(f, ", did you mean `{suggestion}`?")?;
+        }
+        Ok(())
+    }
+}
+
+impl CfgSource {
+    /// Applies the [`UnknownKeys`] policy of the sources.
+    pub(crate) fn check_keys(&self, entries: &[&ConfigEntry]) -> crate::Result<()> {
+        let policy = self.sources.unknown_keys_policy();
+        if policy == UnknownKeys::Ignore {
+            return Ok(());
+        }
+        let unknown = self.unknown_keys(entries);
+        match policy {
+            UnknownKeys::Deny if !unknown.is_empty() => {
+                Err(crate::error::Error::UnknownConfigKeys(unknown))
+            }
+            _ => {
+                for key in unknown {
+                    log::warn!("{key}");
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Every set key that is neither read by one of `entries`, watched or read
+    /// through the context, nor below such a key; an unresolvable placeholder
+    /// makes the check give up.
+    pub(crate) fn unknown_keys(&self, entries: &[&ConfigEntry]) -> Vec<UnknownKey> {
+        let Ok(Leaves(mut keys)) = self.conf.get::<Leaves>("") else {
+            return Vec::new();
+        };
+        keys.sort();
+        let read_keys = self
+            .read_keys
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let known = |key: &str| {
+            let under = |parent: &str| {
+                key.strip_prefix(parent)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with(['.', '[']))
+            };
+            RESERVED.into_iter().any(under)
+                || entries.iter().any(|entry| under(entry.key))
+                || WATCH_KEYS.iter().copied().any(under)
+                || read_keys.iter().any(|read| under(read))
+        };
+
+        keys.into_iter()
+            .filter(|key| !known(key))
+            .map(|key| UnknownKey {
+                source: self
+                    .layers
+                    .iter()
+                    .find(|(_, conf)| provides(conf, &key))
+                    .map_or_else(String::new, |(name, _)| name.to_string()),
+                suggestion: suggest(&key, entries),
+                key,
+            })
+            .collect()
+    }
+}
+
+fn suggest(key: &str, entries: &[&ConfigEntry]) -> Option<&'static str> {
+    let max = (key.chars().count() / 3).max(1);
+    entries
+        .iter()
+        .map(|entry| entry.key)
+        .chain(WATCH_KEYS.iter().copied())
+        .map(|known| (distance(key, known), known))
+        .filter(|(distance, _)| *distance <= max)
+        .min()
+        .map(|(_, key)| key)
+}
+
+/// Levenshtein distance, counting chars.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// The full keys of every value set below a key.
+struct Leaves(Vec<String>);
+
+impl FromConfig for Leaves {
+    fn from_config(
+        context: &mut ConfigContext<'_>,
+        value: Option<ConfigValue<'_>>,
+    ) -> Result<Self, ConfigError> {
+        if value.is_some() {
+            return Ok(Leaves(vec![context.current_key()]));
+        }
+        let map = HashMap::<String, Leaves>::from_config(context, None)?;
+        let list = Vec::<Leaves>::from_config(context, None)?;
+        Ok(Leaves(
+            map.into_values()
+                .chain(list)
+                .flat_map(|leaves| leaves.0)
+                .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::ConfigDefault;
+    use crate::source::ConfigSources;
+
+    const ENTRIES: [ConfigEntry; 2] = [
+        ConfigEntry {
+            bean: "B",
+            field: "name",
+            key: "bbb.name",
+            ty: std::any::type_name::<String>,
+            secret: || false,
+            default: Some(ConfigDefault::Literal("\"b\"")),
+        },
+        ConfigEntry {
+            bean: "Server",
+            field: "tls",
+            key: "server.tls",
+            ty: std::any::type_name::<String>,
+            secret: || false,
+            default: None,
+        },
+    ];
+
+    #[test]
+    fn typos_are_found() {
+        let sources = ConfigSources::new()
+            .memory("app.dir", [("app.dir", ".")])
+            .memory("override", [("bbb.nmae", "x")])
+            .toml(
+                "app.toml",
+                "[bbb]\nname = 'b'\n[server.tls]\ncert = 'a.pem'\nverify = true\n[zzz]\nlist = [1, 2]\n",
+            );
+        let config = CfgSource::new(sources).unwrap();
+        let unknown = config.unknown_keys(&ENTRIES.iter().collect::<Vec<_>>());
+
+        let messages: Vec<_> = unknown.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "unknown config key `bbb.nmae` in override, did you mean `bbb.name`?",
+                "unknown config key `zzz.list[0]` in app.toml",
+                "unknown config key `zzz.list[1]` in app.toml",
+            ]
+        );
+    }
+
+    #[test]
+    fn levenshtein() {
+        assert_eq!(distance("bbb.nmae", "bbb.name"), 2);
+        assert_eq!(distance("", "abc"), 3);
+        assert_eq!(distance("kitten", "sitting"), 3);
+    }
+}
@@ -1,6 +1,7 @@
 use thiserror::Error;
 
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum Error {
     #[error(transparent)]
     Other(#[from] anyhow::Error),
@@ -10,10 +11,67 @@ pub enum Error {
 
     #[error("Initialization for '{0}' has already been done.")]
     DuplicatedInit(&'static str),
+
+    #[error("{0}")]
+    InvalidConfig(crate::config::ConfigIssues),
+
+    /// A bean's init method failed; the config issues of all beans are
+    /// still reported together as [`InvalidConfig`](Error::InvalidConfig).
+    #[error("bean `{bean}` failed to initialize: {source}")]
+    BeanInit {
+        bean: &'static str,
+        source: Box<Error>,
+    },
+
+    #[error("bean `{bean}` field `{field}`: missing config key `{key}`")]
+    MissingConfig {
+        bean: &'static str,
+        field: &'static str,
+        key: String,
+    },
+
+    #[error("bean `{bean}` field `{field}`: env variable `{name}` is not set")]
+    MissingEnv {
+        bean: &'static str,
+        field: &'static str,
+        name: String,
+    },
+
+    #[error("config key `{key}` = `{value}` violates {rule}")]
+    ConfigRule {
+        key: String,
+        value: String,
+        rule: String,
+    },
+
+    #[error("{}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n"))]
+    UnknownConfigKeys(Vec<crate::config::UnknownKey>),
+
+    /// Reserved: beans are built from config alone, without reading each
+    /// other, so no cycle can form while they are initialized.
+    #[error("dependency cycle between beans: {}", .0.join(" -> "))]
+    DependencyCycle(Vec<&'static str>),
+
+    /// Reserved: an alias is an `Alias` impl on the context type, so a missing
+    /// one fails to compile rather than at runtime.
+    #[error("no bean is selected for alias `{alias}` in context `{ctx}`")]
+    AliasNotSelected {
+        alias: &'static str,
+        ctx: &'static str,
+    },
+}
+
+impl Error {
+    pub fn bean_init(bean: &'static str, source: impl Into<Error>) -> Self {
+        Error::BeanInit {
+            bean,
+            source: Box::new(source.into()),
+        }
+    }
 }
 
 impl From<cfg_rs::ConfigError> for Error {
     fn from(err: cfg_rs::ConfigError) -> Self {
         Error::ConfigError(err)
     }
-}
\ No newline at end of file
+}
@@ -1,4 +1,6 @@
-use crate::config::{CfgSource, ConfigSource, IsConfig};
+use crate::config::{
+    CfgSource, ConfigCollector, ConfigIssue, ConfigIssues, ConfigSource, IsConfig,
+};
 use crate::life::InitPhase;
 use std::ops::{Deref, DerefMut};
 
@@ -6,15 +8,41 @@ use std::ops::{Deref, DerefMut};
 pub struct InitCtx {
     phase: InitPhase,
     config: CfgSource,
+    issues: ConfigIssues,
 }
 
 impl InitCtx {
     pub fn new(phase: InitPhase, config: CfgSource) -> Self {
-        Self { phase, config }
+        Self {
+            phase,
+            config,
+            issues: ConfigIssues::default(),
+        }
     }
 
-    pub fn into_phase(self) -> InitPhase {
-        self.phase
+    /// Run once the beans are built; with config issues already found, the
+    /// unknown keys are reported along with them by [`into_parts`](Self::into_parts).
+    pub(crate) fn check_keys(
+        &mut self,
+        entries: &[&crate::schema::ConfigEntry],
+    ) -> crate::Result<()> {
+        use crate::error::Error;
+
+        match self.config.check_keys(entries) {
+            Err(Error::UnknownConfigKeys(keys)) if !self.issues.is_empty() => {
+                self.issues.set_unknown_keys(keys);
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    pub fn into_parts(self) -> crate::Result<(InitPhase, CfgSource)> {
+        if self.issues.is_empty() {
+            Ok((self.phase, self.config))
+        } else {
+            Err(crate::error::Error::InvalidConfig(self.issues))
+        }
     }
 }
 
@@ -27,6 +55,20 @@ impl ConfigSource for InitCtx {
     }
 }
 
+impl ConfigCollector for InitCtx {
+    fn collect(&mut self, issue: ConfigIssue) {
+        self.issues.push(issue);
+    }
+
+    fn env_var(&self, key: &str) -> String {
+        self.config.env_var(key)
+    }
+
+    fn env_value<T: IsConfig>(&self, name: &str) -> crate::Result<T> {
+        self.config.env_value(name)
+    }
+}
+
 impl Deref for InitCtx {
     type Target = InitPhase;
 
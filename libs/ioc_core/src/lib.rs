@@ -1,14 +1,31 @@
-use crate::config::CfgParams;
+use crate::config::{CfgParams, CfgSource};
+use crate::source::ConfigSources;
 use std::ops::{Deref, DerefMut};
+use std::sync::{Mutex, MutexGuard, PoisonError};
 
 pub mod config;
 pub mod error;
 pub mod init;
 pub mod life;
+pub mod many;
 pub mod place;
+pub mod refresh;
+pub mod schema;
+pub mod source;
+pub mod validate;
+
+#[cfg(test)]
+mod testing;
+
+pub use cfg_rs;
 
 pub type Result<T> = std::result::Result<T, error::Error>;
 
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` has no alias `{Name}`",
+    label = "alias `{Name}` is not declared for this context",
+    note = "declare it on the bean with `#[rivete(alias(name = {Name}))]`"
+)]
 pub trait Alias<Name> {
     type Key;
 }
@@ -30,6 +47,29 @@ pub trait Context: Deref<Target = Ctx> {
     {
         <Ctx as Registered<K>>::get(self)
     }
+
+    /// `ctx.get_many::<(B, SomeNeedA)>()` returns `(&B, &SomeNeedA)`.
+    #[inline(always)]
+    fn get_many<'a, K>(&'a self) -> K::Refs
+    where
+        K: many::Beans<'a>,
+    {
+        K::get(self)
+    }
+
+    /// Calls `f` with its arguments resolved from the context,
+    /// e.g. `ctx.call(|b: &B, s: &SomeNeedA| ...)`.
+    ///
+    /// The argument types are the keys, so refresh beans and keyed instances,
+    /// whose bean type is not their key, are read with
+    /// [`get_many`](Self::get_many) instead, see [`many::Handler`].
+    #[inline(always)]
+    fn call<Args, R, F>(&self, f: F) -> R
+    where
+        F: many::Handler<Args, R>,
+    {
+        f.call(self)
+    }
 }
 
 impl<C> Context for C where C: Deref<Target = Ctx> {}
@@ -38,6 +78,11 @@ impl<C> Context for C where C: Deref<Target = Ctx> {}
 /// This trait is unsafe because incorrect implementation may lead to undefined behavior.
 /// Must need add link section to register the bean.
 /// see [linkme](https://crates.io/crates/linkme) and [link mod](link) for more details.
+#[diagnostic::on_unimplemented(
+    message = "no bean is registered under `{K}`",
+    label = "`{K}` is not a registered bean key",
+    note = "derive `Bean` on the type, or check the `key` given to `with(bean(...))`"
+)]
 pub unsafe trait Registered<K: ?Sized> {
     type Bean;
 
@@ -63,30 +108,56 @@ pub mod link {
 
     #[linkme::distributed_slice]
     pub static DROP_METHODS: [DropMethod] = [..];
+
+    pub type RefreshMethod = fn(&mut crate::refresh::RefreshCtx<'_>);
+
+    #[linkme::distributed_slice]
+    pub static REFRESH_METHODS: [RefreshMethod] = [..];
+
+    pub type WatchMethod = fn(&mut crate::refresh::ConfigChange<'_>);
+
+    #[linkme::distributed_slice]
+    pub static WATCH_METHODS: [WatchMethod] = [..];
+
+    #[linkme::distributed_slice]
+    pub static CONFIG_ENTRIES: [crate::schema::ConfigEntry] = [..];
+
+    /// The keys given to `#[rivete(watch = ...)]`, known to the check of
+    /// [`UnknownKeys`](crate::config::UnknownKeys).
+    #[linkme::distributed_slice]
+    pub static WATCH_KEYS: [&'static str] = [..];
 }
 
 #[derive(Debug)]
 pub struct Ctx {
     phase: life::ActivePhase,
+    config: Mutex<CfgSource>,
 }
 
 impl Ctx {
+    /// Builds the beans from the default [`CfgParams`], command line included.
     pub fn new() -> Result<Self> {
         Self::from_cfg(CfgParams::default())
     }
 
     pub fn from_cfg(param: CfgParams) -> Result<Self> {
-        use crate::config::CfgSource;
+        Self::from_sources(param.sources())
+    }
+
+    /// Builds the beans from the given providers instead of the default files.
+    pub fn from_sources(sources: ConfigSources) -> Result<Self> {
         use crate::link::{INIT_METHODS, POST_INIT_METHODS};
 
         let phase = life::InitPhase::take()?;
-        let cfg_source = CfgSource::new(param)?;
+        let cfg_source = CfgSource::new(sources)?;
         let mut ctx = init::InitCtx::new(phase, cfg_source);
 
         for method in INIT_METHODS {
             method(&mut ctx)?;
         }
-        let phase = ctx.into_phase();
+        // once the beans are built, the keys they read through `ctx` are known
+        ctx.check_keys(&schema::entries())?;
+        let (phase, config) = ctx.into_parts()?;
 
         let mut phase = unsafe { phase.complete() };
 
@@ -94,8 +165,69 @@ impl Ctx {
             method(&mut phase);
         }
 
-        Ok(Ctx { phase })
+        Ok(Ctx {
+            phase,
+            config: Mutex::new(config),
+        })
+    }
+
+    /// Re-reads every config source and rebuilds the `#[rivete(refresh)]` beans.
+    ///
+    /// If any of them cannot be rebuilt, all of them keep their current value.
+    pub fn reload_config(&self) -> Result<()> {
+        let changes = {
+            let mut config = self.config();
+            // the sources are read now; a broken version is not retried until it changes again
+            config.changed();
+            let fresh = config.reopen()?;
+            self.refresh_beans(&fresh)?;
+            let changes = config_changes(&config, &fresh);
+            *config = fresh;
+            changes
+        };
+        // without the lock, so a watcher may read or reload the config itself
+        changes.notify(&self.phase);
+        Ok(())
+    }
+
+    /// Reloads the config if one of its files or env variables changed since
+    /// it was loaded, returning whether it did.
+    pub fn poll_config(&self) -> Result<bool> {
+        if !self.config().changed() {
+            return Ok(false);
+        }
+        self.reload_config()?;
+        Ok(true)
+    }
+
+    /// The value and origin of every config key read by a linked bean.
+    pub fn config_report(&self) -> config::ConfigReport {
+        self.config().report(&schema::entries())
+    }
+
+    fn config(&self) -> MutexGuard<'_, CfgSource> {
+        self.config.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn refresh_beans(&self, config: &CfgSource) -> Result<()> {
+        use crate::link::REFRESH_METHODS;
+
+        let mut ctx = refresh::RefreshCtx::new(config);
+        for method in REFRESH_METHODS {
+            method(&mut ctx);
+        }
+        ctx.commit(&self.phase)
+    }
+}
+
+fn config_changes(old: &CfgSource, new: &CfgSource) -> refresh::ConfigChanges {
+    use crate::link::WATCH_METHODS;
+
+    let mut change = refresh::ConfigChange::new(old, new);
+    for method in WATCH_METHODS {
+        method(&mut change);
     }
+    change.into_changes()
 }
 
 impl Deref for Ctx {
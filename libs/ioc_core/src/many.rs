@@ -0,0 +1,59 @@
+//! Several beans in one lookup, see [`Context::get_many`](crate::Context::get_many)
+//! and [`Context::call`](crate::Context::call).
+
+use crate::{Ctx, Registered};
+
+/// A tuple of bean keys, e.g. `(B, SomeNeedA)`.
+pub trait Beans<'a> {
+    /// The tuple of references to the beans.
+    type Refs;
+
+    fn get(ctx: &'a Ctx) -> Self::Refs;
+}
+
+/// A closure whose arguments are beans, e.g. `|b: &B, s: &SomeNeedA| ...`.
+///
+/// Each argument type is used as the key, like `#[inject]` does, so only the
+/// beans stored as their own key can be arguments: a `#[rivete(refresh)]` bean,
+/// stored as `Refresh<T>`, or an `instance` registered under another key
+/// cannot. Read those with [`Beans`], whose keys are given explicitly.
+pub trait Handler<Args, R> {
+    fn call(self, ctx: &Ctx) -> R;
+}
+
+macro_rules! tuples {
+    ($($key:ident),*) => {
+        impl<'a, $($key),*> Beans<'a> for ($($key,)*)
+        where
+            $(Ctx: Registered<$key>, <Ctx as Registered<$key>>::Bean: 'a,)*
+        {
+            type Refs = ($(&'a <Ctx as Registered<$key>>::Bean,)*);
+
+            #[allow(unused_variables, clippy::unused_unit)]
+            fn get(ctx: &'a Ctx) -> Self::Refs {
+                ($(<Ctx as Registered<$key>>::get(ctx),)*)
+            }
+        }
+
+        impl<Fun, R, $($key),*> Handler<($($key,)*), R> for Fun
+        where
+            Fun: FnOnce($(&$key),*) -> R,
+            $(Ctx: Registered<$key, Bean = $key>,)*
+        {
+            #[allow(unused_variables)]
+            fn call(self, ctx: &Ctx) -> R {
+                self($(<Ctx as Registered<$key>>::get(ctx)),*)
+            }
+        }
+    };
+}
+
+tuples!();
+tuples!(K1);
+tuples!(K1, K2);
+tuples!(K1, K2, K3);
+tuples!(K1, K2, K3, K4);
+tuples!(K1, K2, K3, K4, K5);
+tuples!(K1, K2, K3, K4, K5, K6);
+tuples!(K1, K2, K3, K4, K5, K6, K7);
+tuples!(K1, K2, K3, K4, K5, K6, K7, K8);
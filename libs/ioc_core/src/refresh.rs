@@ -0,0 +1,231 @@
+use crate::Ctx;
+use crate::config::{
+    CfgSource, ConfigCollector, ConfigIssue, ConfigIssues, ConfigSource, IsConfig, Rendered,
+};
+use crate::life::ActivePhase;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Storage for a `#[rivete(refresh)]` bean.
+///
+/// Readers take a snapshot with [`Refresh::load`]. A reload swaps in a fully
+/// built replacement, so a snapshot never mixes old and new config values.
+///
+/// The bean is registered as `Refresh<T>`, so `get_by_key::<T>()` returns this
+/// storage and `with` needs `bean(path = T, refresh)`.
+#[derive(Debug)]
+pub struct Refresh<T> {
+    current: RwLock<Arc<T>>,
+}
+
+impl<T> Refresh<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(value)),
+        }
+    }
+
+    /// Returns the current value; it stays valid even if a reload replaces it.
+    pub fn load(&self) -> Arc<T> {
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    pub fn store(&self, value: T) {
+        *self.current.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(value);
+    }
+}
+
+type Commit = Box<dyn FnOnce(&ActivePhase)>;
+
+/// Context handed to every refresh method while the config is reloaded.
+///
+/// Rebuilt beans are only staged here; they are stored once every refreshable
+/// bean has been rebuilt without a config issue.
+pub struct RefreshCtx<'a> {
+    config: &'a CfgSource,
+    issues: ConfigIssues,
+    commits: Vec<Commit>,
+}
+
+impl<'a> RefreshCtx<'a> {
+    pub(crate) fn new(config: &'a CfgSource) -> Self {
+        Self {
+            config,
+            issues: ConfigIssues::default(),
+            commits: Vec::new(),
+        }
+    }
+
+    pub fn stage(&mut self, commit: impl FnOnce(&ActivePhase) + 'static) {
+        self.commits.push(Box::new(commit));
+    }
+
+    pub(crate) fn commit(self, phase: &ActivePhase) -> crate::Result<()> {
+        if !self.issues.is_empty() {
+            return Err(crate::error::Error::InvalidConfig(self.issues));
+        }
+        for commit in self.commits {
+            commit(phase);
+        }
+        Ok(())
+    }
+}
+
+impl ConfigSource for RefreshCtx<'_> {
+    fn get_config<T: IsConfig>(&self, key: impl AsRef<str>) -> crate::Result<T> {
+        self.config.get_config(key)
+    }
+    fn get_config_or<T: IsConfig>(&self, key: impl AsRef<str>, default: T) -> crate::Result<T> {
+        self.config.get_config_or(key, default)
+    }
+}
+
+impl ConfigCollector for RefreshCtx<'_> {
+    fn collect(&mut self, issue: ConfigIssue) {
+        self.issues.push(issue);
+    }
+
+    fn env_var(&self, key: &str) -> String {
+        self.config.env_var(key)
+    }
+
+    fn env_value<T: IsConfig>(&self, name: &str) -> crate::Result<T> {
+        self.config.env_value(name)
+    }
+}
+
+/// Implemented by beans registered with `#[rivete(watch = "some.key")]`.
+///
+/// Called after a reload for every watched key whose value, or subtree,
+/// changed, once the `#[rivete(refresh)]` beans already hold the new config.
+/// A scalar is passed as its text, a table or list written as TOML.
+pub trait OnConfigChange {
+    fn on_config_change(&self, key: &str, old: Option<&str>, new: Option<&str>);
+}
+
+/// Calls the [`OnConfigChange`] of a bean, found again through the phase.
+pub type Notify = fn(&ActivePhase, &str, Option<&str>, Option<&str>);
+
+/// The config before and after a reload.
+///
+/// Changed keys are only staged here; the watchers are called once the reload
+/// released the config, so they may read or reload it themselves.
+pub struct ConfigChange<'a> {
+    old: &'a CfgSource,
+    new: &'a CfgSource,
+    changes: ConfigChanges,
+}
+
+impl<'a> ConfigChange<'a> {
+    pub(crate) fn new(old: &'a CfgSource, new: &'a CfgSource) -> Self {
+        Self {
+            old,
+            new,
+            changes: ConfigChanges(Vec::new()),
+        }
+    }
+
+    /// Stages `notify` for each of `keys` whose value differs between the two configs.
+    pub fn watch(&mut self, keys: &[&'static str], notify: Notify) {
+        for key in keys {
+            let old = value(self.old, key);
+            let new = value(self.new, key);
+            if old != new {
+                self.changes.0.push((key, old, new, notify));
+            }
+        }
+    }
+
+    pub(crate) fn into_changes(self) -> ConfigChanges {
+        self.changes
+    }
+}
+
+/// The watchers staged by a [`ConfigChange`].
+pub(crate) struct ConfigChanges(Vec<(&'static str, Option<String>, Option<String>, Notify)>);
+
+impl ConfigChanges {
+    pub(crate) fn notify(self, phase: &ActivePhase) {
+        for (key, old, new, notify) in self.0 {
+            notify(phase, key, old.as_deref(), new.as_deref());
+        }
+    }
+}
+
+/// A scalar as its text, a table or list as TOML, so a change below `key` counts.
+fn value(config: &CfgSource, key: &str) -> Option<String> {
+    match config.get_config::<Option<String>>(key) {
+        Ok(Some(value)) => Some(value),
+        _ => config
+            .get_config::<Rendered>(key)
+            .ok()
+            .map(|Rendered(value)| value),
+    }
+}
+
+/// Background thread polling the config files and env variables of a [`Ctx`]
+/// and reloading it when one of them changes.
+///
+/// The thread stops when the watcher is dropped.
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ConfigWatcher {
+    pub fn spawn(
+        ctx: Arc<Ctx>,
+        interval: Duration,
+        on_error: impl Fn(crate::error::Error) + Send + 'static,
+    ) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let stop = stop.clone();
+            std::thread::spawn(move || {
+                while !stop.load(Ordering::Acquire) {
+                    std::thread::park_timeout(interval);
+                    if stop.load(Ordering::Acquire) {
+                        break;
+                    }
+                    if let Err(err) = ctx.poll_config() {
+                        on_error(err);
+                    }
+                }
+            })
+        };
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_survives_store() {
+        let value = Refresh::new(String::from("old"));
+        let before = value.load();
+        value.store(String::from("new"));
+        assert_eq!(*before, "old");
+        assert_eq!(*value.load(), "new");
+    }
+}
@@ -0,0 +1,372 @@
+//! The config keys consumed by every bean linked into the binary, rendered as
+//! a JSON Schema or as a commented sample `app.toml`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// A `#[rivete(config)]` field, recorded by the `Bean` derive.
+#[derive(Debug)]
+pub struct ConfigEntry {
+    pub bean: &'static str,
+    pub field: &'static str,
+    pub key: &'static str,
+    pub ty: fn() -> &'static str,
+    /// Whether the field is a [`Secret`](crate::config::Secret), see
+    /// [`SecretProbe`](crate::config::SecretProbe).
+    pub secret: fn() -> bool,
+    pub default: Option<ConfigDefault>,
+}
+
+/// The `default = ...` of a config field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigDefault {
+    /// A literal, as a JSON value that is valid TOML as well, e.g. `"info"` or `1000`.
+    Literal(&'static str),
+    /// Any other expression, as written in the source.
+    Expr(&'static str),
+}
+
+impl ConfigEntry {
+    pub fn ty(&self) -> &'static str {
+        (self.ty)()
+    }
+
+    /// Whether the value is kept out of the [`ConfigReport`](crate::config::ConfigReport).
+    pub fn is_secret(&self) -> bool {
+        (self.secret)()
+    }
+
+    pub fn required(&self) -> bool {
+        self.default.is_none() && !self.ty().starts_with("core::option::Option<")
+    }
+}
+
+/// Every recorded entry, sorted by key.
+pub fn entries() -> Vec<&'static ConfigEntry> {
+    let mut entries: Vec<_> = crate::link::CONFIG_ENTRIES.iter().collect();
+    entries.sort_by_key(|entry| (entry.key, entry.bean, entry.field));
+    entries
+}
+
+pub fn json_schema() -> String {
+    json_schema_of(&entries())
+}
+
+pub fn sample_toml() -> String {
+    sample_toml_of(&entries())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Array,
+    Object,
+}
+
+impl Kind {
+    fn of(ty: &str) -> Self {
+        let ty = ty
+            .strip_prefix("core::option::Option<")
+            .and_then(|ty| ty.strip_suffix('>'))
+            .unwrap_or(ty);
+        match ty {
+            "bool" => Kind::Boolean,
+            "f32" | "f64" => Kind::Number,
+            "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64"
+            | "u128" | "usize" => Kind::Integer,
+            ty if ty.starts_with("alloc::vec::Vec<") => Kind::Array,
+            ty if ty.starts_with("std::collections::hash::map::HashMap<")
+                || ty.starts_with("alloc::collections::btree::map::BTreeMap<") =>
+            {
+                Kind::Object
+            }
+            _ => Kind::String,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Kind::String => "string",
+            Kind::Integer => "integer",
+            Kind::Number => "number",
+            Kind::Boolean => "boolean",
+            Kind::Array => "array",
+            Kind::Object => "object",
+        }
+    }
+
+    fn placeholder(self) -> &'static str {
+        match self {
+            Kind::String => "\"\"",
+            Kind::Integer => "0",
+            Kind::Number => "0.0",
+            Kind::Boolean => "false",
+            Kind::Array => "[]",
+            Kind::Object => "{}",
+        }
+    }
+}
+
+/// Entries grouped by the key they read, keys split into their segments.
+#[derive(Default)]
+struct Node<'a> {
+    entries: Vec<&'a ConfigEntry>,
+    children: BTreeMap<&'a str, Node<'a>>,
+}
+
+impl<'a> Node<'a> {
+    fn build(entries: &[&'a ConfigEntry]) -> Self {
+        let mut root = Node::default();
+        for entry in entries {
+            let node = entry.key.split('.').fold(&mut root, |node, segment| {
+                node.children.entry(segment).or_default()
+            });
+            node.entries.push(entry);
+        }
+        root
+    }
+
+    fn describe(&self) -> String {
+        let users: Vec<_> = self
+            .entries
+            .iter()
+            .map(|entry| format!("{}.{}", entry.bean, entry.field))
+            .collect();
+        format!("{} ({})", users.join(", "), self.entries[0].ty())
+    }
+
+    fn default_value(&self) -> Option<ConfigDefault> {
+        self.entries.iter().find_map(|entry| entry.default)
+    }
+
+    fn required(&self) -> bool {
+        self.entries.iter().any(|entry| entry.required())
+            || self.children.values().any(Node::required)
+    }
+}
+
+pub(crate) fn json_schema_of(entries: &[&ConfigEntry]) -> String {
+    let mut out = String::from("{\"$schema\":\"https://json-schema.org/draft/2020-12/schema\",");
+    write_object(&mut out, &Node::build(entries));
+    out.push('}');
+    out
+}
+
+fn write_object(out: &mut String, node: &Node<'_>) {
+    out.push_str("\"type\":\"object\",\"properties\":{");
+    for (i, (name, child)) in node.children.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_json_string(out, name);
+        out.push_str(":{");
+        if child.entries.is_empty() {
+            write_object(out, child);
+        } else {
+            let kind = Kind::of(child.entries[0].ty());
+            let _ = write!(out, "\"type\":\"{}\",\"description\":", kind.name());
+            write_json_string(out, &child.describe());
+            if let Some(ConfigDefault::Literal(value)) = child.default_value() {
+                let _ = write!(out, ",\"default\":{value}");
+            }
+        }
+        out.push('}');
+    }
+    out.push('}');
+
+    let required: Vec<_> = node
+        .children
+        .iter()
+        .filter(|(_, child)| child.required())
+        .map(|(name, _)| *name)
+        .collect();
+    if !required.is_empty() {
+        out.push_str(",\"required\":[");
+        for (i, name) in required.into_iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            write_json_string(out, name);
+        }
+        out.push(']');
+    }
+}
+
+fn write_json_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+pub(crate) fn sample_toml_of(entries: &[&ConfigEntry]) -> String {
+    let mut out = String::from("# Config keys read by the linked beans.\n");
+    write_table(&mut out, &mut Vec::new(), &Node::build(entries));
+    out
+}
+
+fn write_table<'a>(out: &mut String, path: &mut Vec<&'a str>, node: &Node<'a>) {
+    let values: Vec<_> = node
+        .children
+        .iter()
+        .filter(|(_, child)| !child.entries.is_empty())
+        .collect();
+    if !values.is_empty() {
+        if !path.is_empty() {
+            let header: Vec<_> = path.iter().map(|segment| toml_key(segment)).collect();
+            let _ = writeln!(out, "\n[{}]", header.join("."));
+        }
+        for (name, child) in values {
+            let name = toml_key(name);
+            let _ = writeln!(out, "# {}", child.describe());
+            match child.default_value() {
+                Some(ConfigDefault::Literal(value)) => {
+                    let _ = writeln!(out, "# {name} = {value}");
+                }
+                Some(ConfigDefault::Expr(expr)) => {
+                    let _ = writeln!(out, "# {name} defaults to `{expr}`");
+                }
+                None if child.required() => {
+                    let kind = Kind::of(child.entries[0].ty());
+                    let _ = writeln!(out, "{name} = {}", kind.placeholder());
+                }
+                None => {
+                    let _ = writeln!(out, "# {name} =");
+                }
+            }
+        }
+    }
+    for (name, child) in &node.children {
+        if !child.children.is_empty() {
+            path.push(name);
+            write_table(out, path, child);
+            path.pop();
+        }
+    }
+}
+
+fn toml_key(segment: &str) -> String {
+    let bare = !segment.is_empty()
+        && segment
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        segment.to_string()
+    } else {
+        toml_string(segment)
+    }
+}
+
+/// `s` as a TOML basic string, e.g. `"a \"b\"\n"`.
+pub(crate) fn toml_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\u{8}' => quoted.push_str("\\b"),
+            '\t' => quoted.push_str("\\t"),
+            '\n' => quoted.push_str("\\n"),
+            '\u{c}' => quoted.push_str("\\f"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04X}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENTRIES: [ConfigEntry; 3] = [
+        ConfigEntry {
+            bean: "B",
+            field: "name",
+            key: "bbb.name",
+            ty: std::any::type_name::<String>,
+            secret: || false,
+            default: None,
+        },
+        ConfigEntry {
+            bean: "Web",
+            field: "port",
+            key: "web.port",
+            ty: std::any::type_name::<u16>,
+            secret: || false,
+            default: Some(ConfigDefault::Literal("8080")),
+        },
+        ConfigEntry {
+            bean: "Web",
+            field: "timeout",
+            key: "web.timeout",
+            ty: std::any::type_name::<Option<u64>>,
+            secret: || false,
+            default: None,
+        },
+    ];
+
+    fn entries() -> Vec<&'static ConfigEntry> {
+        ENTRIES.iter().collect()
+    }
+
+    #[test]
+    fn sample() {
+        assert_eq!(
+            sample_toml_of(&entries()),
+            r#"# Config keys read by the linked beans.
+
+[bbb]
+# B.name (alloc::string::String)
+name = ""
+
+[web]
+# Web.port (u16)
+# port = 8080
+# Web.timeout (core::option::Option<u64>)
+# timeout =
+"#
+        );
+    }
+
+    #[test]
+    fn schema() {
+        assert_eq!(
+            json_schema_of(&entries()),
+            concat!(
+                r#"{"$schema":"https://json-schema.org/draft/2020-12/schema","type":"object","#,
+                r#""properties":{"bbb":{"type":"object","properties":{"name":{"type":"string","#,
+                r#""description":"B.name (alloc::string::String)"}},"required":["name"]},"#,
+                r#""web":{"type":"object","properties":{"port":{"type":"integer","#,
+                r#""description":"Web.port (u16)","default":8080},"timeout":{"type":"integer","#,
+                r#""description":"Web.timeout (core::option::Option<u64>)"}}}},"required":["bbb"]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn toml_strings() {
+        assert_eq!(toml_string("plain"), r#""plain""#);
+        assert_eq!(
+            toml_string("a \"b\"\tc:\\dir\n\u{1}\u{7f}é"),
+            r#""a \"b\"\tc:\\dir\n\u0001\u007Fé""#
+        );
+    }
+}
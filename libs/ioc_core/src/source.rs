@@ -0,0 +1,734 @@
+//! Config providers, composed in priority order by [`ConfigSources`].
+
+use crate::config::UnknownKeys;
+use cfg_rs::source::{ConfigSourceAdaptor, ConfigSourceParser};
+use cfg_rs::{ConfigError, Configuration};
+use std::fmt::{Debug, Formatter};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::SystemTime;
+
+pub use cfg_rs::source::ConfigSourceBuilder;
+
+#[cfg(feature = "remote")]
+mod remote;
+#[cfg(feature = "remote")]
+pub use remote::RemoteSource;
+
+/// A layer of config values.
+///
+/// Unlike [`ConfigSource`](crate::config::ConfigSource), this trait is object
+/// safe, so providers of any kind can be stacked at runtime.
+pub trait ConfigProvider: Send + Sync {
+    /// Names the provider in error messages.
+    fn name(&self) -> &str;
+
+    /// Adds every value of this layer to `values`; called twice per load, the
+    /// second time to tell which provider a value comes from.
+    fn load(&self, values: &mut ConfigSourceBuilder<'_>) -> crate::Result<()>;
+
+    /// Whether the values changed since the previous call, or since the
+    /// provider was created; a reload is triggered when any provider changed.
+    fn changed(&self) -> bool {
+        false
+    }
+}
+
+/// Config providers in priority order: a value from a provider added earlier
+/// hides the values of the ones added after it.
+#[derive(Clone, Default)]
+pub struct ConfigSources {
+    providers: Vec<Arc<dyn ConfigProvider>>,
+    prefix_env: Option<String>,
+    /// Replaces the process environment, see [`env_vars`](Self::env_vars).
+    vars: Option<Arc<[(String, String)]>>,
+    base_dir: Option<PathBuf>,
+    unknown_keys: UnknownKeys,
+}
+
+impl Debug for ConfigSources {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.providers.iter().map(|provider| provider.name()))
+            .finish()
+    }
+}
+
+impl ConfigSources {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn provider(mut self, provider: impl ConfigProvider + 'static) -> Self {
+        self.providers.push(Arc::new(provider));
+        self
+    }
+
+    /// Fixed key/value pairs, handy in tests.
+    pub fn memory<K: ToString, V: ToString>(
+        self,
+        name: &str,
+        values: impl IntoIterator<Item = (K, V)>,
+    ) -> Self {
+        self.provider(MemorySource::new(name, values))
+    }
+
+    /// A TOML document held in memory.
+    pub fn toml(self, name: &str, content: &str) -> Self {
+        self.provider(TomlSource::new(name, content))
+    }
+
+    /// Env variables starting with `{prefix}_`, e.g. `APP_BBB_NAME` for `bbb.name`.
+    ///
+    /// The first prefix added is the one suggested for missing keys.
+    pub fn env(self, prefix: &str) -> Self {
+        self.env_source(EnvSource::new(prefix))
+    }
+
+    /// Like [`env`](Self::env), reading `vars` instead of the process
+    /// environment; the `#[rivete(env = ...)]` fields of beans read them too.
+    pub fn env_vars<K: ToString, V: ToString>(
+        mut self,
+        prefix: &str,
+        vars: impl IntoIterator<Item = (K, V)>,
+    ) -> Self {
+        let vars: Vec<_> = vars
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        self.vars.get_or_insert_with(|| vars.clone().into());
+        self.env_source(EnvSource::with_vars(prefix, vars))
+    }
+
+    fn env_source(mut self, source: EnvSource) -> Self {
+        self.prefix_env.get_or_insert_with(|| source.prefix.clone());
+        self.provider(source)
+    }
+
+    /// `--key=value` or `-D key=value` command line overrides; other arguments are ignored.
+    pub fn args<S: AsRef<str>>(self, args: impl IntoIterator<Item = S>) -> Self {
+        self.provider(ArgsSource::new(args))
+    }
+
+    /// A `.env` file read like [`env`](Self::env) with the same prefix.
+    pub fn dotenv(self, path: impl Into<PathBuf>, prefix: &str) -> Self {
+        self.provider(DotenvSource::new(path, prefix))
+    }
+
+    /// A config file, skipped if it does not exist.
+    pub fn file(self, path: impl Into<PathBuf>) -> Self {
+        self.provider(FileSource::new(path))
+    }
+
+    /// A config file that fails the load if it does not exist.
+    pub fn required_file(self, path: impl Into<PathBuf>) -> Self {
+        self.provider(FileSource::required(path))
+    }
+
+    /// The directory relative [`ConfigPath`](crate::config::ConfigPath)
+    /// values are resolved against; reading one without it is an error.
+    pub fn base_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.base_dir = Some(dir.into());
+        self
+    }
+
+    pub(crate) fn base_dir_path(&self) -> Option<&Path> {
+        self.base_dir.as_deref()
+    }
+
+    /// What to do with keys that no bean reads, warn by default.
+    pub fn unknown_keys(mut self, policy: UnknownKeys) -> Self {
+        self.unknown_keys = policy;
+        self
+    }
+
+    pub(crate) fn unknown_keys_policy(&self) -> UnknownKeys {
+        self.unknown_keys
+    }
+
+    pub(crate) fn prefix_env(&self) -> Option<&str> {
+        self.prefix_env.as_deref()
+    }
+
+    /// The env variable `name`, from the variables given to
+    /// [`env_vars`](Self::env_vars) if any.
+    pub(crate) fn var(&self, name: &str) -> Option<String> {
+        match &self.vars {
+            Some(vars) => vars
+                .iter()
+                .find(|(var, _)| var == name)
+                .map(|(_, value)| value.clone()),
+            None => std::env::var(name).ok(),
+        }
+    }
+
+    /// Loads every provider into a fresh configuration, and each one into a
+    /// configuration of its own.
+    pub(crate) fn load(&self) -> crate::Result<Loaded> {
+        let mut conf = Configuration::new();
+        let mut layers = Vec::with_capacity(self.providers.len());
+        for provider in &self.providers {
+            conf = conf.register_source(Layer(provider.clone()))?;
+            let layer = Configuration::new().register_source(Layer(provider.clone()))?;
+            layers.push((provider.name().to_string(), layer));
+        }
+        Ok(Loaded { conf, layers })
+    }
+
+    /// Asks every provider, so each one resets its own change flag.
+    pub(crate) fn changed(&self) -> bool {
+        let mut changed = false;
+        for provider in &self.providers {
+            changed |= provider.changed();
+        }
+        changed
+    }
+}
+
+/// The configuration of every provider together, and of each one on its own
+/// to tell where a value comes from.
+pub(crate) struct Loaded {
+    pub(crate) conf: Configuration,
+    pub(crate) layers: Vec<(String, Configuration)>,
+}
+
+/// Adapts a provider to the source trait of cfg-rs.
+struct Layer(Arc<dyn ConfigProvider>);
+
+impl cfg_rs::source::ConfigSource for Layer {
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+
+    fn load(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        self.0.load(builder).map_err(|err| match err {
+            crate::error::Error::ConfigError(err) => err,
+            err => ConfigError::ConfigCause(Box::new(err)),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct MemorySource {
+    name: String,
+    values: Vec<(String, String)>,
+}
+
+impl MemorySource {
+    pub fn new<K: ToString, V: ToString>(
+        name: &str,
+        values: impl IntoIterator<Item = (K, V)>,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            values: values
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl ConfigProvider for MemorySource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn load(&self, values: &mut ConfigSourceBuilder<'_>) -> crate::Result<()> {
+        for (key, value) in &self.values {
+            values.set(key.as_str(), value.clone());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct TomlSource {
+    name: String,
+    content: String,
+}
+
+impl TomlSource {
+    pub fn new(name: &str, content: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            content: content.to_string(),
+        }
+    }
+}
+
+impl ConfigProvider for TomlSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn load(&self, values: &mut ConfigSourceBuilder<'_>) -> crate::Result<()> {
+        parse::<cfg_rs::source::toml::Toml>(&self.content, values)
+    }
+}
+
+fn parse<P: ConfigSourceParser>(
+    content: &str,
+    values: &mut ConfigSourceBuilder<'_>,
+) -> crate::Result<()> {
+    Ok(P::parse_source(content)?.convert_source(values)?)
+}
+
+#[derive(Debug)]
+pub struct EnvSource {
+    name: String,
+    prefix: String,
+    /// Fixed variables read instead of the process environment.
+    vars: Option<Vec<(String, String)>>,
+    seen: Mutex<Vec<(String, String)>>,
+}
+
+impl EnvSource {
+    pub fn new(prefix: &str) -> Self {
+        let prefix = prefix.to_uppercase();
+        Self {
+            name: format!("env:{prefix}_*"),
+            seen: Mutex::new(prefixed_env(&prefix, std::env::vars())),
+            vars: None,
+            prefix,
+        }
+    }
+
+    /// Reads `vars` instead of the process environment; they never change.
+    pub fn with_vars<K: ToString, V: ToString>(
+        prefix: &str,
+        vars: impl IntoIterator<Item = (K, V)>,
+    ) -> Self {
+        let prefix = prefix.to_uppercase();
+        Self {
+            name: format!("env:{prefix}_*"),
+            vars: Some(
+                vars.into_iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect(),
+            ),
+            seen: Mutex::default(),
+            prefix,
+        }
+    }
+
+    fn vars(&self) -> Vec<(String, String)> {
+        match &self.vars {
+            Some(vars) => prefixed_env(&self.prefix, vars.iter().cloned()),
+            None => prefixed_env(&self.prefix, std::env::vars()),
+        }
+    }
+}
+
+impl ConfigProvider for EnvSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn load(&self, values: &mut ConfigSourceBuilder<'_>) -> crate::Result<()> {
+        set_env(values, &self.prefix, self.vars());
+        Ok(())
+    }
+
+    fn changed(&self) -> bool {
+        if self.vars.is_some() {
+            return false;
+        }
+        let env = self.vars();
+        let mut seen = self.seen.lock().unwrap_or_else(PoisonError::into_inner);
+        if *seen == env {
+            return false;
+        }
+        *seen = env;
+        true
+    }
+}
+
+/// Sets `{prefix}_BBB_NAME` as `bbb.name`, skipping variables without the prefix.
+fn set_env(
+    values: &mut ConfigSourceBuilder<'_>,
+    prefix: &str,
+    vars: impl IntoIterator<Item = (String, String)>,
+) {
+    let strip = format!("{prefix}_");
+    for (name, value) in vars {
+        if let Some(key) = name.strip_prefix(&strip) {
+            values.set(key.to_lowercase().replace('_', ".").as_str(), value);
+        }
+    }
+}
+
+fn prefixed_env(
+    prefix: &str,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Vec<(String, String)> {
+    let prefix = format!("{prefix}_");
+    let mut env: Vec<_> = vars
+        .into_iter()
+        .filter(|(name, _)| name.starts_with(&prefix))
+        .collect();
+    env.sort();
+    env
+}
+
+/// The config related parts of a command line.
+#[derive(Debug, Default, PartialEq)]
+pub struct CommandLine {
+    /// `--key=value`, `-D key=value` and `-Dkey=value`, in order.
+    pub overrides: Vec<(String, String)>,
+    /// `--config <path>` or `--config=<path>`.
+    pub config: Option<PathBuf>,
+    /// `--profile <name>` or `--profile=<name>`.
+    pub profile: Option<String>,
+}
+
+impl CommandLine {
+    /// Picks the config arguments out of `args`, ignoring everything else.
+    pub fn parse<S: AsRef<str>>(args: impl IntoIterator<Item = S>) -> Self {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let arg = arg.as_ref();
+            let mut value = |inline: Option<&str>| match inline {
+                Some(value) => Some(value.to_string()),
+                None => args.next().map(|value| value.as_ref().to_string()),
+            };
+            if let Some(define) = arg.strip_prefix("-D") {
+                let define = if define.is_empty() {
+                    value(None)
+                } else {
+                    Some(define.to_string())
+                };
+                if let Some((key, value)) = define.as_deref().and_then(|d| d.split_once('=')) {
+                    parsed.overrides.push((key.to_string(), value.to_string()));
+                }
+                continue;
+            }
+            let Some(long) = arg.strip_prefix("--") else {
+                continue;
+            };
+            let (name, inline) = match long.split_once('=') {
+                Some((name, inline)) => (name, Some(inline)),
+                None => (long, None),
+            };
+            match name {
+                "config" => parsed.config = value(inline).map(PathBuf::from),
+                "profile" => parsed.profile = value(inline),
+                key => {
+                    if let Some(inline) = inline {
+                        parsed.overrides.push((key.to_string(), inline.to_string()));
+                    }
+                }
+            }
+        }
+        parsed
+    }
+}
+
+/// Config overrides given on the command line, see [`CommandLine`].
+#[derive(Debug)]
+pub struct ArgsSource {
+    values: Vec<(String, String)>,
+}
+
+impl ArgsSource {
+    pub fn new<S: AsRef<str>>(args: impl IntoIterator<Item = S>) -> Self {
+        Self::from(CommandLine::parse(args))
+    }
+}
+
+impl From<CommandLine> for ArgsSource {
+    fn from(command_line: CommandLine) -> Self {
+        Self {
+            values: command_line.overrides,
+        }
+    }
+}
+
+impl ConfigProvider for ArgsSource {
+    fn name(&self) -> &str {
+        "args"
+    }
+
+    fn load(&self, values: &mut ConfigSourceBuilder<'_>) -> crate::Result<()> {
+        for (key, value) in &self.values {
+            values.set(key.as_str(), value.clone());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct FileSource {
+    name: String,
+    path: PathBuf,
+    required: bool,
+    seen: Mutex<Option<SystemTime>>,
+}
+
+impl FileSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self {
+            name: path.display().to_string(),
+            seen: Mutex::new(modified(&path)),
+            required: false,
+            path,
+        }
+    }
+
+    /// Like [`new`](Self::new), but a missing file is an error.
+    pub fn required(path: impl Into<PathBuf>) -> Self {
+        Self {
+            required: true,
+            ..Self::new(path)
+        }
+    }
+}
+
+impl ConfigProvider for FileSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn load(&self, values: &mut ConfigSourceBuilder<'_>) -> crate::Result<()> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return match self.required {
+                    true => Err(ConfigError::ConfigFileNotExists(self.path.clone()).into()),
+                    false => Ok(()),
+                };
+            }
+            Err(err) => return Err(anyhow::Error::new(err).into()),
+        };
+        match self.path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml" | "tml") => parse::<cfg_rs::source::toml::Toml>(&content, values),
+            #[cfg(feature = "yaml")]
+            Some("yaml" | "yml") => parse::<cfg_rs::source::yaml::Yaml>(&content, values),
+            #[cfg(feature = "json")]
+            Some("json") => parse::<cfg_rs::source::json::Json>(&content, values),
+            _ => Err(ConfigError::ConfigFileNotSupported(self.path.clone()).into()),
+        }
+    }
+
+    fn changed(&self) -> bool {
+        let modified = modified(&self.path);
+        let mut seen = self.seen.lock().unwrap_or_else(PoisonError::into_inner);
+        if *seen == modified {
+            return false;
+        }
+        *seen = modified;
+        true
+    }
+}
+
+/// A `.env` file, read like the env variables of an [`EnvSource`] with the
+/// same prefix but without touching the process environment.
+///
+/// Lines are `NAME=value`, optionally preceded by `export`; values may be
+/// single or double quoted, and `#` starts a comment outside quotes. A missing
+/// file is empty.
+#[derive(Debug)]
+pub struct DotenvSource {
+    name: String,
+    path: PathBuf,
+    prefix: String,
+    seen: Mutex<Option<SystemTime>>,
+}
+
+impl DotenvSource {
+    pub fn new(path: impl Into<PathBuf>, prefix: &str) -> Self {
+        let path = path.into();
+        Self {
+            name: path.display().to_string(),
+            seen: Mutex::new(modified(&path)),
+            prefix: prefix.to_uppercase(),
+            path,
+        }
+    }
+}
+
+impl ConfigProvider for DotenvSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn load(&self, values: &mut ConfigSourceBuilder<'_>) -> crate::Result<()> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(anyhow::Error::new(err).into()),
+        };
+        set_env(values, &self.prefix, parse_dotenv(&content));
+        Ok(())
+    }
+
+    fn changed(&self) -> bool {
+        let modified = modified(&self.path);
+        let mut seen = self.seen.lock().unwrap_or_else(PoisonError::into_inner);
+        if *seen == modified {
+            return false;
+        }
+        *seen = modified;
+        true
+    }
+}
+
+fn parse_dotenv(content: &str) -> Vec<(String, String)> {
+    let mut vars = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+        let name = name.trim();
+        if name.is_empty() || name.starts_with('#') {
+            continue;
+        }
+        let value = value.trim();
+        let value = match value.chars().next() {
+            Some('"') => double_quoted(&value[1..]),
+            Some('\'') => match value[1..].find('\'') {
+                Some(end) => value[1..=end].to_string(),
+                None => value.to_string(),
+            },
+            _ => match value.find(" #") {
+                Some(comment) => value[..comment].trim_end().to_string(),
+                None => value.to_string(),
+            },
+        };
+        vars.push((name.to_string(), value));
+    }
+    vars
+}
+
+/// The value up to the closing quote, with `\n`, `\"` and `\\` unescaped.
+fn double_quoted(value: &str) -> String {
+    let mut out = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => break,
+            '\\' => match chars.next() {
+                Some('n') => out.push('\n'),
+                Some(c) => out.push(c),
+                None => break,
+            },
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    path.metadata().and_then(|meta| meta.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed;
+
+    impl ConfigProvider for Fixed {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        fn load(&self, values: &mut ConfigSourceBuilder<'_>) -> crate::Result<()> {
+            values.set("web.port", 9000);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn priority_order() {
+        let conf = ConfigSources::new()
+            .args(["serve", "--web.host=args", "--verbose"])
+            .memory("test", [("web.name", "memory"), ("web.host", "memory")])
+            .env_vars(
+                "ioc_sources_test",
+                [
+                    ("IOC_SOURCES_TEST_WEB_HOST", "env"),
+                    ("IOC_SOURCES_TEST_WEB_WORKERS", "8"),
+                    ("OTHER_WEB_PORT", "1"),
+                ],
+            )
+            .toml(
+                "inline",
+                "[web]\nhost = 'toml'\nname = 'toml'\nworkers = 4\n",
+            )
+            .provider(Fixed)
+            .load()
+            .unwrap()
+            .conf;
+
+        assert_eq!(conf.get::<String>("web.host").unwrap(), "args");
+        assert_eq!(conf.get::<String>("web.name").unwrap(), "memory");
+        assert_eq!(conf.get::<u32>("web.workers").unwrap(), 8);
+        assert_eq!(conf.get::<u16>("web.port").unwrap(), 9000);
+    }
+
+    #[test]
+    fn command_line() {
+        let parsed = CommandLine::parse([
+            "serve",
+            "--bbb.name=foo",
+            "-D",
+            "web.port=80",
+            "-Dweb.host=example.com",
+            "--config",
+            "conf/prod.toml",
+            "--profile=prod",
+            "--verbose",
+            "-D",
+        ]);
+        assert_eq!(
+            parsed,
+            CommandLine {
+                overrides: vec![
+                    ("bbb.name".to_string(), "foo".to_string()),
+                    ("web.port".to_string(), "80".to_string()),
+                    ("web.host".to_string(), "example.com".to_string()),
+                ],
+                config: Some(PathBuf::from("conf/prod.toml")),
+                profile: Some("prod".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn dotenv() {
+        let vars = parse_dotenv(concat!(
+            "# local overrides\n",
+            "APP_BBB_NAME=plain # comment\n",
+            "export APP_WEB_HOST = 'single # kept'\n",
+            "APP_WEB_BANNER=\"line\\nbreak \\\"quoted\\\"\"\n",
+            "not a variable\n",
+        ));
+        assert_eq!(
+            vars,
+            [
+                ("APP_BBB_NAME".to_string(), "plain".to_string()),
+                ("APP_WEB_HOST".to_string(), "single # kept".to_string()),
+                (
+                    "APP_WEB_BANNER".to_string(),
+                    "line\nbreak \"quoted\"".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_toml() {
+        let Err(err) = ConfigSources::new().toml("inline", "[web").load() else {
+            panic!("invalid toml loaded");
+        };
+        assert!(
+            matches!(err, crate::error::Error::ConfigError(_)),
+            "{err:?}"
+        );
+    }
+}
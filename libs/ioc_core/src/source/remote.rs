@@ -0,0 +1,328 @@
+//! A subtree of a consul-style key/value store, read over HTTP.
+
+use super::{ConfigProvider, ConfigSourceBuilder};
+use crate::config::{Secret, Url};
+use anyhow::{Context as _, anyhow, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+/// Reads every key below `prefix` from `GET {endpoint}/v1/kv/{prefix}?recurse`,
+/// `app/bbb/name` becoming `bbb.name` for the prefix `app`.
+///
+/// Each change check fetches the subtree again, and a load uses the subtree
+/// fetched last, only fetching it when there is none yet. When the endpoint
+/// cannot be reached at startup, the [snapshot](Self::snapshot) written by a
+/// previous run is used instead.
+///
+/// Only plain `http` is spoken, as to a local agent.
+#[derive(Debug)]
+pub struct RemoteSource {
+    name: String,
+    url: String,
+    prefix: String,
+    token: Option<Secret<String>>,
+    agent: ureq::Agent,
+    snapshot: Option<PathBuf>,
+    last: Mutex<Option<String>>,
+}
+
+impl RemoteSource {
+    pub fn new(endpoint: &str, prefix: &str) -> crate::Result<Self> {
+        let url = Url::parse(endpoint)
+            .filter(|url| url.scheme() == "http")
+            .ok_or_else(|| anyhow!("remote config endpoint `{endpoint}` is not an http url"))?;
+        let prefix = prefix.trim_matches('/').to_string();
+        let name = format!("{}/v1/kv/{prefix}", url.as_str().trim_end_matches('/'));
+        Ok(Self {
+            url: format!("{name}?recurse"),
+            name,
+            prefix,
+            token: None,
+            agent: agent(Duration::from_secs(5)),
+            snapshot: None,
+            last: Mutex::new(None),
+        })
+    }
+
+    /// Sent as `X-Consul-Token`.
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(Secret::new(token.into()));
+        self
+    }
+
+    /// Bounds each request as a whole, 5 seconds by default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.agent = agent(timeout);
+        self
+    }
+
+    /// A file holding the last fetched subtree, written after every fetch and
+    /// read when the endpoint is unreachable at startup.
+    pub fn snapshot(mut self, path: impl Into<PathBuf>) -> Self {
+        self.snapshot = Some(path.into());
+        self
+    }
+
+    /// Fetches the subtree and remembers it, returning whether it differs
+    /// from the one fetched before.
+    fn fetch(&self) -> crate::Result<(String, bool)> {
+        let body = self.get()?;
+        // rejects a broken body before it replaces a good snapshot
+        entries(&body, &self.prefix)?;
+        let mut last = self.last.lock().unwrap_or_else(PoisonError::into_inner);
+        let changed = last.as_deref() != Some(body.as_str());
+        if changed {
+            if let Some(path) = &self.snapshot {
+                std::fs::write(path, &body)
+                    .with_context(|| format!("cannot write snapshot {}", path.display()))?;
+            }
+            *last = Some(body.clone());
+        }
+        Ok((body, changed))
+    }
+
+    fn get(&self) -> anyhow::Result<String> {
+        let mut request = self
+            .agent
+            .get(&self.url)
+            .header("Accept", "application/json");
+        if let Some(token) = &self.token {
+            request = request.header("X-Consul-Token", token.expose());
+        }
+        let mut response = request
+            .call()
+            .with_context(|| format!("cannot fetch {}", self.name))?;
+        match response.status().as_u16() {
+            200 => Ok(response.body_mut().read_to_string()?),
+            // nothing stored below the prefix yet
+            404 => Ok("[]".to_string()),
+            status => bail!("{} answered {status}", self.name),
+        }
+    }
+
+    fn snapshot_body(&self) -> Option<String> {
+        std::fs::read_to_string(self.snapshot.as_ref()?).ok()
+    }
+}
+
+fn agent(timeout: Duration) -> ureq::Agent {
+    ureq::Agent::config_builder()
+        .timeout_global(Some(timeout))
+        .http_status_as_error(false)
+        .build()
+        .into()
+}
+
+impl ConfigProvider for RemoteSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn load(&self, values: &mut ConfigSourceBuilder<'_>) -> crate::Result<()> {
+        let last = self
+            .last
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let body = match last {
+            Some(body) => body,
+            None => match self.fetch() {
+                Ok((body, _)) => body,
+                Err(err) => self.snapshot_body().ok_or(err)?,
+            },
+        };
+        for (key, value) in entries(&body, &self.prefix)? {
+            values.set(key.as_str(), value);
+        }
+        Ok(())
+    }
+
+    /// An unreachable endpoint is not a change; the values fetched last stay.
+    fn changed(&self) -> bool {
+        self.fetch().is_ok_and(|(_, changed)| changed)
+    }
+}
+
+/// An item of a `?recurse` response; folders have no value.
+#[derive(Deserialize)]
+struct KvEntry {
+    #[serde(rename = "Key")]
+    key: String,
+    #[serde(rename = "Value")]
+    value: Option<String>,
+}
+
+/// The config keys and values of a `?recurse` response.
+fn entries(body: &str, prefix: &str) -> anyhow::Result<Vec<(String, String)>> {
+    let items: Vec<KvEntry> =
+        serde_json::from_str(body).context("expected a json array of keys")?;
+    let mut entries = Vec::new();
+    for KvEntry { key, value } in items {
+        let Some(value) = value else {
+            continue;
+        };
+        // `apple/x` is not below the prefix `app`
+        let Some(key) = key
+            .strip_prefix(prefix)
+            .filter(|rest| prefix.is_empty() || rest.is_empty() || rest.starts_with('/'))
+        else {
+            continue;
+        };
+        let key = key.trim_matches('/').replace('/', ".");
+        if key.is_empty() {
+            continue;
+        }
+        let value = STANDARD
+            .decode(value)
+            .with_context(|| format!("invalid base64 value for `{key}`"))?;
+        let value = String::from_utf8(value).context("value is not utf-8")?;
+        entries.push((key, value));
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::ConfigSources;
+    use crate::testing::TempDir;
+    use std::io::{BufRead, Write};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+
+    /// A stand-in store answering with whatever `body` holds, `None` meaning 500.
+    fn serve(body: Arc<Mutex<Option<String>>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = String::new();
+                let mut reader = std::io::BufReader::new(&stream);
+                while reader.read_line(&mut request).unwrap() > 2 {
+                    request.clear();
+                }
+                let response = match body.lock().unwrap().clone() {
+                    Some(body) => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{body}",
+                        body.len()
+                    ),
+                    None => "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\n\r\n"
+                        .to_string(),
+                };
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        endpoint
+    }
+
+    fn kv(pairs: &[(&str, &str)]) -> String {
+        let items: Vec<_> = pairs
+            .iter()
+            .map(|(key, value)| match value {
+                &"" => format!(r#"{{"Key":"{key}","Value":null,"Flags":0}}"#),
+                value => format!(
+                    r#"{{"LockIndex":0,"Key":"{key}","Flags":0,"Value":"{}"}}"#,
+                    STANDARD.encode(value)
+                ),
+            })
+            .collect();
+        format!("[{}]", items.join(","))
+    }
+
+    #[test]
+    fn polls_and_falls_back_to_the_snapshot() {
+        let body = Arc::new(Mutex::new(Some(kv(&[
+            ("app/", ""),
+            ("app/bbb/name", "remote"),
+            ("app/web/port", "8080"),
+        ]))));
+        let endpoint = serve(body.clone());
+        let dir = TempDir::new("remote");
+        let snapshot = dir.join("snapshot.json");
+
+        let source = RemoteSource::new(&endpoint, "app")
+            .unwrap()
+            .snapshot(&snapshot);
+        let sources = ConfigSources::new().provider(source);
+        let conf = sources.load().unwrap().conf;
+        assert_eq!(conf.get::<String>("bbb.name").unwrap(), "remote");
+        assert_eq!(conf.get::<u16>("web.port").unwrap(), 8080);
+        assert!(!sources.changed());
+
+        *body.lock().unwrap() = Some(kv(&[("app/bbb/name", "changed")]));
+        assert!(sources.changed());
+        assert!(!sources.changed());
+        assert_eq!(
+            sources
+                .load()
+                .unwrap()
+                .conf
+                .get::<String>("bbb.name")
+                .unwrap(),
+            "changed"
+        );
+
+        // the store goes down: the running source keeps its values
+        *body.lock().unwrap() = None;
+        assert!(!sources.changed());
+        assert_eq!(
+            sources
+                .load()
+                .unwrap()
+                .conf
+                .get::<String>("bbb.name")
+                .unwrap(),
+            "changed"
+        );
+
+        // and a fresh one starts from the snapshot
+        let restarted = RemoteSource::new(&endpoint, "app")
+            .unwrap()
+            .snapshot(&snapshot);
+        let conf = ConfigSources::new()
+            .provider(restarted)
+            .load()
+            .unwrap()
+            .conf;
+        assert_eq!(conf.get::<String>("bbb.name").unwrap(), "changed");
+
+        let without_snapshot = RemoteSource::new(&endpoint, "app").unwrap();
+        assert!(
+            ConfigSources::new()
+                .provider(without_snapshot)
+                .load()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn entries_of_a_response() {
+        let body = r#"[
+            {"Key": "app/", "Value": null},
+            {"Key": "app/web/port", "Value": "ODA4MA==", "ModifyIndex": 1e3},
+            {"Key": "app/bbb/name", "Value": "8J+Ygg=="},
+            {"Key": "app/\ud83d\ude02", "Value": "eA=="},
+            {"Key": "apple/x", "Value": "eA=="}
+        ]"#;
+        assert_eq!(
+            entries(body, "app").unwrap(),
+            [
+                ("web.port".to_string(), "8080".to_string()),
+                ("bbb.name".to_string(), "\u{1f602}".to_string()),
+                ("\u{1f602}".to_string(), "x".to_string()),
+            ]
+        );
+        assert_eq!(
+            entries(r#"[{"Key": "a/b", "Value": "eA=="}]"#, "").unwrap(),
+            [("a.b".to_string(), "x".to_string())]
+        );
+        assert!(entries(r#"[{"Key": "app/a", "Value": 12-3}]"#, "app").is_err());
+        assert!(entries(r#"[{"Key": "app/a", "Value": "not base64!"}]"#, "app").is_err());
+        assert!(RemoteSource::new("https://consul:8500", "app").is_err());
+    }
+}
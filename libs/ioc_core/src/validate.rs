@@ -0,0 +1,166 @@
+//! Checks run on config values by `#[rivete(config(name = "...", validate(...)))]`.
+//!
+//! Each check fails with [`Error::ConfigRule`], naming the key, the value and
+//! the rule it breaks.
+
+use crate::error::Error;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+
+pub use regex::Regex;
+
+fn violated(key: &str, value: &impl Debug, rule: String) -> Error {
+    Error::ConfigRule {
+        key: key.to_string(),
+        value: format!("{value:?}"),
+        rule,
+    }
+}
+
+/// `range(min = .., max = ..)`, both bounds inclusive and optional.
+pub fn range<T: PartialOrd + Debug>(
+    key: &str,
+    value: &T,
+    min: Option<T>,
+    max: Option<T>,
+) -> crate::Result<()> {
+    let low = min.as_ref().is_some_and(|min| value < min);
+    let high = max.as_ref().is_some_and(|max| value > max);
+    if !low && !high {
+        return Ok(());
+    }
+    let bound = |bound: &Option<T>| bound.as_ref().map(|b| format!("{b:?}")).unwrap_or_default();
+    Err(violated(
+        key,
+        value,
+        format!("range({}..={})", bound(&min), bound(&max)),
+    ))
+}
+
+/// Values that `non_empty` applies to.
+pub trait IsEmpty {
+    fn is_empty(&self) -> bool;
+}
+
+impl IsEmpty for str {
+    fn is_empty(&self) -> bool {
+        self.trim().is_empty()
+    }
+}
+
+impl IsEmpty for String {
+    fn is_empty(&self) -> bool {
+        self.as_str().is_empty()
+    }
+}
+
+impl<T> IsEmpty for Vec<T> {
+    fn is_empty(&self) -> bool {
+        Vec::is_empty(self)
+    }
+}
+
+impl<K, V, S> IsEmpty for HashMap<K, V, S> {
+    fn is_empty(&self) -> bool {
+        HashMap::is_empty(self)
+    }
+}
+
+impl<K, V> IsEmpty for BTreeMap<K, V> {
+    fn is_empty(&self) -> bool {
+        BTreeMap::is_empty(self)
+    }
+}
+
+impl<T: IsEmpty> IsEmpty for crate::config::Secret<T> {
+    fn is_empty(&self) -> bool {
+        self.expose().is_empty()
+    }
+}
+
+/// `non_empty`; blank strings count as empty.
+pub fn non_empty<T: IsEmpty + Debug + ?Sized>(key: &str, value: &T) -> crate::Result<()> {
+    if value.is_empty() {
+        Err(violated(key, &value, "non_empty".to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+/// `one_of(..)`.
+pub fn one_of<T: PartialEq<U> + Debug, U: Debug>(
+    key: &str,
+    value: &T,
+    allowed: &[U],
+) -> crate::Result<()> {
+    if allowed.iter().any(|allowed| value == allowed) {
+        Ok(())
+    } else {
+        Err(violated(key, value, format!("one_of({allowed:?})")))
+    }
+}
+
+/// `regex = ".."`, which must match the whole value. `regex` is `pattern`
+/// anchored at both ends, compiled once per field by the derive.
+pub fn regex<T: AsRef<str> + Debug>(
+    key: &str,
+    value: &T,
+    pattern: &str,
+    regex: &Regex,
+) -> crate::Result<()> {
+    if regex.is_match(value.as_ref()) {
+        Ok(())
+    } else {
+        Err(violated(key, value, format!("regex({pattern:?})")))
+    }
+}
+
+/// `custom = path::to::check`, a `fn(&T) -> Result<(), String>`.
+pub fn custom<T: Debug>(
+    key: &str,
+    value: &T,
+    name: &str,
+    check: impl FnOnce(&T) -> Result<(), String>,
+) -> crate::Result<()> {
+    check(value).map_err(|reason| violated(key, value, format!("{name}: {reason}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(result: crate::Result<()>) -> String {
+        result.unwrap_err().to_string()
+    }
+
+    #[test]
+    fn rules() {
+        assert!(range("web.port", &80u16, Some(1), Some(1024)).is_ok());
+        assert_eq!(
+            message(range("web.port", &0u16, Some(1), None)),
+            "config key `web.port` = `0` violates range(1..=)"
+        );
+        assert_eq!(
+            message(non_empty("bbb.name", " ")),
+            "config key `bbb.name` = `\" \"` violates non_empty"
+        );
+        assert!(one_of("log.level", &"info".to_string(), &["info", "debug"]).is_ok());
+        assert_eq!(
+            message(one_of("log.level", &"loud".to_string(), &["info", "debug"])),
+            "config key `log.level` = `\"loud\"` violates one_of([\"info\", \"debug\"])"
+        );
+        let host = Regex::new(r"^(?:[a-z.]+)$").unwrap();
+        assert!(regex("web.host", &"a.example.com", r"[a-z.]+", &host).is_ok());
+        assert_eq!(
+            message(regex("web.host", &"a.example.com/", r"[a-z.]+", &host)),
+            "config key `web.host` = `\"a.example.com/\"` violates regex(\"[a-z.]+\")"
+        );
+        assert_eq!(
+            message(custom("n", &3, "even", |n| match n % 2 {
+                0 => Ok(()),
+                _ => Err("odd".to_string()),
+            })),
+            "config key `n` = `3` violates even: odd"
+        );
+    }
+}
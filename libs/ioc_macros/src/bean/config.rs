@@ -1,10 +1,79 @@
 use darling::{FromMeta, ast::NestedMeta};
-use syn::Expr;
+use proc_macro2::TokenStream;
+use quote::{ToTokens, quote};
+use syn::{Expr, Lit, LitStr, Path};
 
 #[derive(Debug, FromMeta, PartialEq)]
 pub(crate) struct Named {
     pub name: String,
-    pub default: Option<Expr>,
+    pub default: Option<Box<Expr>>,
+    /// `None` when the key is missing; implied by an `Option<..>` field type.
+    #[darling(default)]
+    pub optional: bool,
+    pub validate: Option<Box<Validate>>,
+}
+
+/// `validate(...)`, the checks run on the value once it is read.
+#[derive(Debug, FromMeta, PartialEq, Default)]
+pub(crate) struct Validate {
+    pub range: Option<Range>,
+    #[darling(default)]
+    pub non_empty: bool,
+    pub one_of: Option<OneOf>,
+    pub regex: Option<Pattern>,
+    pub custom: Option<Path>,
+}
+
+/// `regex = ".."`, compiled while expanding the derive so a bad pattern is a
+/// compile error.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Pattern(pub LitStr);
+
+impl Pattern {
+    /// The pattern matching the whole value.
+    pub(crate) fn anchored(&self) -> String {
+        format!("^(?:{})$", self.0.value())
+    }
+}
+
+impl FromMeta for Pattern {
+    fn from_value(value: &Lit) -> darling::Result<Self> {
+        let Lit::Str(pattern) = value else {
+            return Err(darling::Error::unexpected_lit_type(value));
+        };
+        let pattern = Pattern(pattern.clone());
+        match regex::Regex::new(&pattern.anchored()) {
+            Ok(_) => Ok(pattern),
+            Err(err) => {
+                Err(darling::Error::custom(format!("invalid regex: {err}")).with_span(&pattern.0))
+            }
+        }
+    }
+}
+
+#[derive(Debug, FromMeta, PartialEq)]
+pub(crate) struct Range {
+    pub min: Option<Expr>,
+    pub max: Option<Expr>,
+}
+
+/// `one_of("a", "b")`, the allowed literal values.
+#[derive(Debug, PartialEq)]
+pub(crate) struct OneOf(pub Vec<Lit>);
+
+impl FromMeta for OneOf {
+    fn from_list(items: &[NestedMeta]) -> darling::Result<Self> {
+        items
+            .iter()
+            .map(|item| match item {
+                NestedMeta::Lit(lit) => Ok(lit.clone()),
+                NestedMeta::Meta(meta) => {
+                    Err(darling::Error::custom("expected a literal").with_span(meta))
+                }
+            })
+            .collect::<darling::Result<_>>()
+            .map(OneOf)
+    }
 }
 
 #[derive(Debug, PartialEq, Default)]
@@ -32,10 +101,61 @@ impl FromMeta for Config {
         Ok(Self::Named(Named {
             name: value.to_string(),
             default: None,
+            optional: false,
+            validate: None,
         }))
     }
 }
 
+/// The `ConfigDefault` recorded for a `default = ...`: a literal as the JSON
+/// value it stands for, which is valid TOML as well, anything else as source text.
+pub(crate) fn config_default(default: &Expr, ioc: &TokenStream) -> TokenStream {
+    let literal = match default {
+        Expr::Lit(lit) => json_value(&lit.lit),
+        _ => None,
+    };
+    match literal {
+        Some(value) => quote! { Some(::#ioc::prelude::ConfigDefault::Literal(#value)) },
+        None => {
+            let expr = default.to_token_stream().to_string();
+            quote! { Some(::#ioc::prelude::ConfigDefault::Expr(#expr)) }
+        }
+    }
+}
+
+/// `None` for the literals JSON has no value for, e.g. byte strings.
+fn json_value(lit: &Lit) -> Option<String> {
+    match lit {
+        Lit::Str(lit) => Some(json_string(&lit.value())),
+        Lit::Char(lit) => Some(json_string(&lit.value().to_string())),
+        Lit::Int(lit) => match lit.base10_parse::<i128>() {
+            Ok(value) => Some(value.to_string()),
+            Err(_) => lit.base10_parse::<u128>().ok().map(|value| value.to_string()),
+        },
+        Lit::Float(lit) => lit
+            .base10_parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite())
+            .map(|value| format!("{value:?}")),
+        Lit::Bool(lit) => Some(lit.value.to_string()),
+        _ => None,
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
 
 #[cfg(test)]
 mod tests {
@@ -51,6 +171,8 @@ mod tests {
             Named {
                 name: "test".to_string(),
                 default: None,
+                optional: false,
+                validate: None,
             }
         );
 
@@ -60,7 +182,9 @@ mod tests {
             named,
             Named {
                 name: "test".to_string(),
-                default: Some(parse_quote!(1 + 2)),
+                default: Some(Box::new(parse_quote!(1 + 2))),
+                optional: false,
+                validate: None,
             }
         );
     }
@@ -84,6 +208,8 @@ mod tests {
             Config::Named(Named {
                 name: "test".to_string(),
                 default: None,
+                optional: false,
+                validate: None,
             })
         );
     }
@@ -96,6 +222,8 @@ mod tests {
             Config::Named(Named {
                 name: "test".to_string(),
                 default: None,
+                optional: false,
+                validate: None,
             })
         );
     }
@@ -107,7 +235,9 @@ mod tests {
             config_meta,
             Config::Named(Named {
                 name: "test".to_string(),
-                default: Some(parse_quote!(12)),
+                default: Some(Box::new(parse_quote!(12))),
+                optional: false,
+                validate: None,
             })
         );
     }
@@ -119,8 +249,89 @@ mod tests {
             config_meta,
             Config::Named(Named {
                 name: "test".to_string(),
-                default: Some(parse_quote!(1234)),
+                default: Some(Box::new(parse_quote!(1234))),
+                optional: false,
+                validate: None,
             })
         );
     }
+    #[test]
+    fn test_config_optional() {
+        let attr: Attribute = parse_quote!( #[config(name = "web.proxy", optional)] );
+        let config_meta = Config::from_meta(&attr.meta).unwrap();
+        assert_eq!(
+            config_meta,
+            Config::Named(Named {
+                name: "web.proxy".to_string(),
+                default: None,
+                optional: true,
+                validate: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_config_validate() {
+        let attr: Attribute = parse_quote!(
+            #[config(
+                name = "web.port",
+                validate(range(min = 1, max = 1024), non_empty, one_of(80, 443), custom = check)
+            )]
+        );
+        let config_meta = Config::from_meta(&attr.meta).unwrap();
+        assert_eq!(
+            config_meta,
+            Config::Named(Named {
+                name: "web.port".to_string(),
+                default: None,
+                optional: false,
+                validate: Some(Box::new(Validate {
+                    range: Some(Range {
+                        min: Some(parse_quote!(1)),
+                        max: Some(parse_quote!(1024)),
+                    }),
+                    non_empty: true,
+                    one_of: Some(OneOf(vec![parse_quote!(80), parse_quote!(443)])),
+                    regex: None,
+                    custom: Some(parse_quote!(check)),
+                })),
+            })
+        );
+    }
+
+    #[test]
+    fn test_config_regex() {
+        let attr: Attribute =
+            parse_quote!( #[config(name = "web.host", validate(regex = "[a-z.]+"))] );
+        let Config::Named(Named {
+            validate: Some(validate),
+            ..
+        }) = Config::from_meta(&attr.meta).unwrap()
+        else {
+            panic!("expected a named config with checks");
+        };
+        assert_eq!(validate.regex.unwrap().anchored(), "^(?:[a-z.]+)$");
+
+        let attr: Attribute =
+            parse_quote!( #[config(name = "web.host", validate(regex = "[a-z"))] );
+        let err = Config::from_meta(&attr.meta).unwrap_err().to_string();
+        assert!(err.starts_with("invalid regex: "), "{err}");
+    }
+
+    #[test]
+    fn test_json_default() {
+        let json = |expr: Expr| match &expr {
+            Expr::Lit(lit) => json_value(&lit.lit),
+            _ => None,
+        };
+        assert_eq!(json(parse_quote!("a \"b\"\n")), Some(r#""a \"b\"\n""#.to_string()));
+        assert_eq!(json(parse_quote!(r"c:\dir")), Some(r#""c:\\dir""#.to_string()));
+        assert_eq!(json(parse_quote!('c')), Some(r#""c""#.to_string()));
+        assert_eq!(json(parse_quote!(1_000u32)), Some("1000".to_string()));
+        assert_eq!(json(parse_quote!(0xff)), Some("255".to_string()));
+        assert_eq!(json(parse_quote!(1.)), Some("1.0".to_string()));
+        assert_eq!(json(parse_quote!(2.5e3f64)), Some("2500.0".to_string()));
+        assert_eq!(json(parse_quote!(true)), Some("true".to_string()));
+        assert_eq!(json(parse_quote!(b"bytes")), None);
+    }
 }
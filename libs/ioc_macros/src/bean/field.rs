@@ -1,8 +1,8 @@
-use crate::bean::config::{Config, Named};
+use crate::bean::config::{Config, Named, OneOf, Range, Validate, config_default};
 use darling::{Error, FromField};
 use proc_macro2::{Ident, TokenStream};
-use quote::{ToTokens, quote};
-use syn::{Expr, Type};
+use quote::{ToTokens, format_ident, quote};
+use syn::{Expr, GenericArgument, PathArguments, Type};
 
 #[derive(Debug, FromField, PartialEq)]
 #[darling(attributes(rivete), and_then = Self::validate)]
@@ -10,10 +10,41 @@ pub(crate) struct Field {
     ty: Type,
     ident: Option<Ident>,
     config: Config,
+    /// Read the value through `serde::Deserialize`, see `Serde`.
+    #[darling(default)]
+    serde: bool,
+    /// Read from this environment variable instead of the config.
+    env: Option<String>,
+    /// Computed by this expression, which may read the config through `ctx`;
+    /// it may return a `Result`, whose error is reported like a config issue.
+    value: Option<Expr>,
 }
 
 impl Field {
     fn validate(self) -> darling::Result<Self> {
+        let sources = [
+            self.config != Config::Default,
+            self.env.is_some(),
+            self.value.is_some(),
+        ];
+        if sources.into_iter().filter(|set| *set).count() > 1 {
+            return Err(Error::custom(
+                "`config`, `env` and `value` are exclusive, a field has a single source.",
+            ));
+        }
+        if self.serde && self.config == Config::Default {
+            return Err(Error::custom(
+                "`serde` only applies to config fields, add `config` to the field.",
+            ));
+        }
+        if matches!(&self.config, Config::Named(Named { optional: true, .. }))
+            && !is_option(&self.ty)
+        {
+            return Err(Error::custom(
+                "`optional` leaves the field `None` when the key is missing, the field must be an `Option<..>`.",
+            )
+            .with_span(&self.ty));
+        }
         if self.ident.is_none() && self.config == Config::Trivial {
             return Err(Error::custom(
                 "Trivial config cannot be used for tuple struct fields! You must provide a name for the config field.",
@@ -22,45 +53,329 @@ impl Field {
         Ok(self)
     }
 
-    pub(crate) fn as_init(&self) -> FieldInit<'_> {
+    pub(crate) fn as_init<'a>(
+        &'a self,
+        bean: &'a Ident,
+        index: usize,
+        prefix: Option<&'a str>,
+    ) -> FieldInit<'a> {
         FieldInit {
             field: self,
+            bean,
+            index,
+            prefix,
+            var: format_ident!("field_{}", index),
+        }
+    }
+}
+
+/// Whether `ty` is written as an `Option<..>`; an alias of it is not seen.
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+/// The `T` of an `Option<T>` written as such.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    match &path.path.segments.last()?.arguments {
+        PathArguments::AngleBracketed(args) if is_option(ty) => {
+            args.args.iter().find_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
         }
+        _ => None,
     }
 }
 
 pub(crate) struct FieldInit<'a> {
     field: &'a Field,
+    /// The struct ident.
+    bean: &'a Ident,
+    index: usize,
+    /// The struct level `config_prefix`, keys are relative to it.
+    prefix: Option<&'a str>,
+    var: Ident,
 }
 
-impl ToTokens for FieldInit<'_> {
-    fn to_tokens(&self, tokens: &mut TokenStream) {
-        let Field {
-            ty: _ty,
-            ident,
-            config,
-        } = self.field;
-
-        let initializer = match config {
-            Config::Default => quote! { ::core::default::Default::default() },
-            Config::Trivial => quote! { ctx.get_config::<_>(#ident)? },
-            Config::Named(Named { name, default }) => {
-                if let Some(value) = default {
-                    match value {
-                        Expr::Lit(lit) => {
-                            quote! { ctx.get_config_or::<_>(#name, #lit.into())? }
-                        }
-                        other => {
-                            quote! { ctx.get_config_or::<_>(#name, #other)? }
-                        }
+impl FieldInit<'_> {
+    fn name(&self) -> String {
+        match &self.field.ident {
+            Some(ident) => ident.to_string(),
+            None => self.index.to_string(),
+        }
+    }
+
+    fn key(&self) -> Option<String> {
+        let key = match &self.field.config {
+            Config::Default => return None,
+            Config::Trivial => self.name(),
+            Config::Named(Named { name, .. }) => name.clone(),
+        };
+        match self.prefix {
+            Some(prefix) => Some(format!("{prefix}.{key}")),
+            None => Some(key),
+        }
+    }
+
+    /// Whether a missing key leaves the field `None`, implied by an
+    /// `Option<..>` type; `optional` is only accepted on one.
+    fn optional(&self) -> bool {
+        is_option(&self.field.ty)
+    }
+
+    /// The variable holding the looked up or computed value, if this field
+    /// reads config or env or has a `value`; it is an `Option` until every
+    /// lookup is done.
+    pub(crate) fn checked_var(&self) -> Option<&Ident> {
+        (self.key().is_some() || self.field.env.is_some() || self.field.value.is_some())
+            .then_some(&self.var)
+    }
+
+    /// The associated fn evaluating `value = ...`, named after the field.
+    fn value_fn(&self) -> Option<Ident> {
+        self.field
+            .value
+            .as_ref()
+            .map(|_| format_ident!("__rivete_value_{}", self.name()))
+    }
+
+    /// Defines the `value = ...` expression next to the struct, so the names
+    /// it uses resolve against the bean's own module.
+    pub(crate) fn value_def(&self, ioc: &TokenStream) -> Option<TokenStream> {
+        let value_fn = self.value_fn()?;
+        let value = &self.field.value;
+        let ty = &self.field.ty;
+        Some(quote! {
+            #[doc(hidden)]
+            #[allow(unused_variables)]
+            fn #value_fn(
+                ctx: &mut impl ::#ioc::prelude::ConfigCollector,
+            ) -> ::#ioc::prelude::Result<#ty> {
+                #[allow(unused_imports)]
+                use ::#ioc::prelude::ConfigSource as _;
+                ::#ioc::prelude::FieldValue::into_result(#value)
+            }
+        })
+    }
+
+    /// The associated fn calling `validate(custom = ...)`, named after the field.
+    fn check_fn(&self) -> Option<Ident> {
+        match &self.field.config {
+            Config::Named(Named {
+                validate: Some(validate),
+                ..
+            }) if validate.custom.is_some() => {
+                Some(format_ident!("__rivete_check_{}", self.name()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Defines the call of the `custom` validator next to the struct, so its
+    /// path, `Self::..` included, resolves against the bean's own module.
+    pub(crate) fn check_def(&self) -> Option<TokenStream> {
+        let check_fn = self.check_fn()?;
+        let Config::Named(Named {
+            validate: Some(validate),
+            default,
+            ..
+        }) = &self.field.config
+        else {
+            return None;
+        };
+        let check = validate.custom.as_ref()?;
+        let ty = &self.field.ty;
+        // like `binding`, an optional field without a default is checked unwrapped
+        let ty = match default.is_none() && self.optional() {
+            true => option_inner(ty).unwrap_or(ty),
+            false => ty,
+        };
+        Some(quote! {
+            #[doc(hidden)]
+            #[allow(clippy::ptr_arg)]
+            fn #check_fn(
+                value: &#ty,
+            ) -> ::core::result::Result<(), ::std::string::String> {
+                #check(value)
+            }
+        })
+    }
+
+    /// Looks the config or env value up, recording a failure on `ctx` instead
+    /// of returning early, or computes the `value = ...` expression.
+    pub(crate) fn binding(&self) -> Option<TokenStream> {
+        let var = &self.var;
+        if let Some(value_fn) = self.value_fn() {
+            let bean = self.bean;
+            let field = self.name();
+            return Some(quote! {
+                let #var = #bean::#value_fn(ctx);
+                let #var = ctx.value_field(BEAN, #field, #var);
+            });
+        }
+        if let Some(name) = &self.field.env {
+            let field = self.name();
+            return Some(quote! {
+                let #var = ctx.env_field(BEAN, #field, #name, ctx.env_value::<_>(#name));
+            });
+        }
+
+        let key = self.key()?;
+        let field = self.name();
+
+        let default = match &self.field.config {
+            Config::Named(Named {
+                default: Some(default),
+                ..
+            }) => match &**default {
+                Expr::Lit(lit) => Some(quote! { #lit.into() }),
+                other => Some(quote! { #other }),
+            },
+            _ => None,
+        };
+
+        let optional = default.is_none() && self.optional();
+        let lookup = match (default, self.field.serde) {
+            (Some(default), false) => quote! { ctx.get_config_or::<_>(#key, #default) },
+            (None, false) if optional => quote! { ctx.get_optional::<_>(#key) },
+            (None, false) => quote! { ctx.get_config::<_>(#key) },
+            (Some(default), true) => quote! {
+                ctx.get_config_or::<Serde<_>>(#key, Serde(#default)).map(Serde::into_inner)
+            },
+            (None, true) if optional => quote! {
+                ctx.get_optional::<Serde<_>>(#key).map(|value| value.map(Serde::into_inner))
+            },
+            (None, true) => quote! {
+                ctx.get_config::<Serde<_>>(#key).map(Serde::into_inner)
+            },
+        };
+
+        let checks = match optional {
+            true => self.checks(&key, quote! { value }).map(|checks| {
+                quote! {
+                    if let Some(value) = &value {
+                        #checks
                     }
-                } else {
-                    quote! { ctx.get_config::<_>(#name)?}
                 }
+            }),
+            false => self.checks(&key, quote! { &value }),
+        };
+        let lookup = match checks {
+            Some(checks) => quote! {
+                #lookup.and_then(|value| {
+                    #checks
+                    Ok(value)
+                })
+            },
+            None => lookup,
+        };
+
+        Some(quote! {
+            let #var = ctx.config_field(BEAN, #field, #key, #lookup);
+        })
+    }
+
+    /// The `validate(...)` checks on `value`, a reference to the value, each
+    /// returning early on failure.
+    fn checks(&self, key: &str, value: TokenStream) -> Option<TokenStream> {
+        let Config::Named(Named {
+            validate: Some(validate),
+            ..
+        }) = &self.field.config
+        else {
+            return None;
+        };
+        let Validate {
+            range,
+            non_empty,
+            one_of,
+            regex,
+            custom,
+        } = &**validate;
+
+        let mut checks = Vec::new();
+        if let Some(Range { min, max }) = range {
+            let min = min
+                .as_ref()
+                .map_or(quote! { None }, |min| quote! { Some(#min) });
+            let max = max
+                .as_ref()
+                .map_or(quote! { None }, |max| quote! { Some(#max) });
+            checks.push(quote! { validate::range(#key, #value, #min, #max)?; });
+        }
+        if *non_empty {
+            checks.push(quote! { validate::non_empty(#key, #value)?; });
+        }
+        if let Some(OneOf(allowed)) = one_of {
+            checks.push(quote! { validate::one_of(#key, #value, &[#(#allowed),*])?; });
+        }
+        if let Some(pattern) = regex {
+            let anchored = pattern.anchored();
+            let pattern = &pattern.0;
+            checks.push(quote! {
+                validate::regex(#key, #value, #pattern, {
+                    static REGEX: ::std::sync::LazyLock<validate::Regex> =
+                        ::std::sync::LazyLock::new(|| validate::Regex::new(#anchored).unwrap());
+                    &REGEX
+                })?;
+            });
+        }
+        if let (Some(check), Some(check_fn)) = (custom, self.check_fn()) {
+            let name = check.to_token_stream().to_string().replace(' ', "");
+            let bean = self.bean;
+            checks.push(quote! { validate::custom(#key, #value, #name, #bean::#check_fn)?; });
+        }
+        Some(quote! { #(#checks)* })
+    }
+
+    /// The `ConfigEntry` describing this field for the config schema.
+    pub(crate) fn entry(&self, bean: &str, ioc: &TokenStream) -> Option<TokenStream> {
+        let key = self.key()?;
+        let field = self.name();
+        let ty = &self.field.ty;
+
+        let default = match &self.field.config {
+            Config::Named(Named {
+                default: Some(default),
+                ..
+            }) => config_default(default, ioc),
+            _ => quote! { None },
+        };
+
+        Some(quote! {
+            ::#ioc::prelude::ConfigEntry {
+                bean: #bean,
+                field: #field,
+                key: #key,
+                ty: ::core::any::type_name::<#ty>,
+                secret: || {
+                    use ::#ioc::prelude::{IsSecret as _, NotSecret as _};
+                    (&::#ioc::prelude::SecretProbe::<#ty>::NEW).is_secret()
+                },
+                default: #default,
             }
+        })
+    }
+}
+
+impl ToTokens for FieldInit<'_> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let initializer = match self.checked_var() {
+            Some(var) => quote! { #var },
+            None => quote! { ::core::default::Default::default() },
         };
 
-        if let Some(field_name) = ident {
+        if let Some(field_name) = &self.field.ident {
             tokens.extend(quote! { #field_name : #initializer })
         } else {
             tokens.extend(initializer)
@@ -135,4 +450,55 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn env_and_value() -> Result<(), String> {
+        let field = Field::from_field(&parse_quote!(
+            #[rivete(env = "HOSTNAME")]
+            host: String
+        ))
+        .map_err(|err| err.to_string())?;
+        assert_eq!(field.env.as_deref(), Some("HOSTNAME"));
+
+        let field = Field::from_field(&parse_quote!(
+            #[rivete(value = std::process::id())]
+            pid: u32
+        ))
+        .map_err(|err| err.to_string())?;
+        assert_eq!(field.value, Some(parse_quote!(std::process::id())));
+
+        let Err(err) = Field::from_field(&parse_quote!(
+            #[rivete(config, env = "HOSTNAME")]
+            host: String
+        )) else {
+            return Err("Expected exclusive sources".to_string());
+        };
+        assert_eq!(
+            err.to_string(),
+            "`config`, `env` and `value` are exclusive, a field has a single source."
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn optional_needs_an_option() -> Result<(), String> {
+        let field = Field::from_field(&parse_quote!(
+            #[rivete(config(name = "web.proxy", optional))]
+            proxy: Option<String>
+        ))
+        .map_err(|err| err.to_string())?;
+        assert!(field.as_init(&parse_quote!(Web), 0, None).optional());
+
+        let Err(err) = Field::from_field(&parse_quote!(
+            #[rivete(config(name = "web.proxy", optional))]
+            proxy: String
+        )) else {
+            return Err("Expected an `Option` field".to_string());
+        };
+        assert_eq!(
+            err.to_string(),
+            "`optional` leaves the field `None` when the key is missing, the field must be an `Option<..>`."
+        );
+        Ok(())
+    }
 }
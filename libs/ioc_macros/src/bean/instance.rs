@@ -0,0 +1,29 @@
+use darling::FromMeta;
+
+/// An extra registration of the same struct under another key, reading its
+/// config below another prefix.
+#[derive(Debug, FromMeta, PartialEq)]
+pub(crate) struct Instance {
+    pub key: syn::Path,
+    pub config_prefix: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::{Attribute, parse_quote};
+
+    #[test]
+    fn test_instance() {
+        let attr: Attribute =
+            parse_quote!( #[instance(key = ReplicaDb, config_prefix = "db.replica")] );
+        let instance = Instance::from_meta(&attr.meta).unwrap();
+        assert_eq!(
+            instance,
+            Instance {
+                key: parse_quote!(ReplicaDb),
+                config_prefix: Some("db.replica".to_string()),
+            }
+        );
+    }
+}
@@ -1,16 +1,20 @@
 mod config;
 mod field;
 mod alias;
+mod instance;
 
 use crate::{
     bean::field::Field,
-    bean::alias::Alias
+    bean::alias::Alias,
+    bean::instance::Instance,
 };
 use darling::{Error, FromDeriveInput, Result, ast::Data, ast::Style};
 use proc_macro2::{Ident, TokenStream};
 use quote::{ToTokens, format_ident, quote};
 use syn::Path;
 
+pub(crate) use config::config_default;
+
 pub(crate) fn resolve_ioc_crate(ioc_crate: &Option<Path>) -> Result<TokenStream> {
     if let Some(ioc_crate) = ioc_crate {
         Ok(quote! { #ioc_crate })
@@ -44,24 +48,67 @@ pub(crate) struct Bean {
     name: Option<String>,
     #[darling(default)]
     ioc_crate: Option<Path>,
+
+    /// Rebuild this bean from config on reload, see `Ctx::reload_config`.
+    #[darling(default)]
+    refresh: bool,
+
+    /// Config keys reported to the bean's `OnConfigChange` impl on reload.
+    #[darling(multiple)]
+    watch: Vec<String>,
+
+    /// Prefix of the keys read by the `#[rivete(config)]` fields.
+    #[darling(default)]
+    config_prefix: Option<String>,
+
+    /// Further registrations of this struct, each with its own config prefix.
+    #[darling(multiple)]
+    instance: Vec<Instance>,
 }
 
 struct BuildInit<'a> {
     ident: &'a Ident,
     fields: &'a Data<(), Field>,
     ioc: &'a TokenStream,
+    prefix: Option<&'a str>,
 }
 
 impl BuildInit<'_> {
     fn generate(&self) -> Result<TokenStream> {
-        let Self { ident, fields, ioc } = *self;
+        let Self {
+            ident,
+            fields,
+            ioc,
+            prefix,
+        } = *self;
 
         if !fields.is_struct() {
             Err(Error::unsupported_shape("only struct is supported").with_span(ident))
         } else {
             let struct_fields = fields.as_ref().take_struct().expect("not here!");
 
-            let field_initializers = struct_fields.iter().map(|f| f.as_init());
+            let field_initializers: Vec<_> = struct_fields
+                .iter()
+                .enumerate()
+                .map(|(index, f)| f.as_init(ident, index, prefix))
+                .collect();
+
+            let bindings = field_initializers.iter().filter_map(|f| f.binding());
+
+            let checked: Vec<_> = field_initializers
+                .iter()
+                .filter_map(|f| f.checked_var())
+                .collect();
+
+            let check = if checked.is_empty() {
+                quote! {}
+            } else {
+                quote! {
+                    let (#(Some(#checked),)*) = (#(#checked,)*) else {
+                        return None;
+                    };
+                }
+            };
 
             let initializers = quote! {
                 #(#field_initializers),*
@@ -70,7 +117,6 @@ impl BuildInit<'_> {
             let initializer = match struct_fields.style {
                 Style::Tuple => {
                     quote! {
-                        use ::#ioc::prelude::*;
                         #ident(
                             #initializers
                         )
@@ -78,7 +124,6 @@ impl BuildInit<'_> {
                 }
                 Style::Struct => {
                     quote! {
-                        use ::#ioc::prelude::*;
                         #ident{
                             #initializers
                         }
@@ -91,33 +136,42 @@ impl BuildInit<'_> {
             Ok(quote! {
                  {
                      use ::#ioc::prelude::*;
-                     #initializer
+                     #(#bindings)*
+                     #check
+                     Some(#initializer)
                  }
             })
         }
     }
 }
 
+/// One `Registered` impl of a bean, with the storage backing it.
+pub(crate) struct Registration<'a> {
+    pub ident: &'a Ident,
+    pub key: TokenStream,
+    pub mod_ident: Ident,
+    pub bean_name: String,
+    /// Body of `fn build(ctx: &mut impl ConfigCollector) -> Option<#ident>`.
+    pub build: TokenStream,
+    pub config_entries: TokenStream,
+    pub refresh: bool,
+    pub watch: &'a [String],
+}
+
 impl Bean {
     pub(crate) fn generate(&self) -> Result<TokenStream> {
         let Self {
             ref ident,
-            ref data,
             ref name,
             ref alias,
             ref ioc_crate,
+            ref config_prefix,
+            ref instance,
+            ..
         } = *self;
 
         let ioc = resolve_ioc_crate(ioc_crate)?;
 
-        let build_method = BuildInit {
-            ident,
-            fields: data,
-            ioc: &ioc,
-        };
-
-        let build_method = build_method.generate()?;
-
         let key = if let Some(key) = name {
             quote! { #key }
         } else {
@@ -126,6 +180,39 @@ impl Bean {
 
         let mod_ident = format_ident!("{}_bean_register", key.to_string().to_lowercase());
 
+        let primary = self
+            .registration(
+                key,
+                mod_ident,
+                ident.to_string(),
+                config_prefix.as_deref(),
+                &ioc,
+            )?
+            .generate(&ioc);
+
+        let instances = instance
+            .iter()
+            .map(|instance| {
+                let key = &instance.key;
+                let key_name = key
+                    .segments
+                    .last()
+                    .map(|segment| segment.ident.to_string())
+                    .unwrap_or_default();
+                Ok(self
+                    .registration(
+                        quote! { #key },
+                        format_ident!("{}_bean_register", key_name.to_lowercase()),
+                        format!("{ident}({key_name})"),
+                        instance.config_prefix.as_deref(),
+                        &ioc,
+                    )?
+                    .generate(&ioc))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let value_fns = value_fns(ident, &self.data, &ioc)?;
+
         let alias_impl = if let Some(alias) = alias {
             alias.generate(ident, &ioc)?
         } else {
@@ -133,23 +220,161 @@ impl Bean {
         };
 
         Ok(quote! {
+            #primary
+
+            #(#instances)*
+
+            #value_fns
+
+            #alias_impl
+        })
+    }
+
+    fn registration(
+        &self,
+        key: TokenStream,
+        mod_ident: Ident,
+        bean_name: String,
+        prefix: Option<&str>,
+        ioc: &TokenStream,
+    ) -> Result<Registration<'_>> {
+        let Self {
+            ref ident,
+            ref data,
+            refresh,
+            ref watch,
+            ..
+        } = *self;
+
+        let build = BuildInit {
+            ident,
+            fields: data,
+            ioc,
+            prefix,
+        }
+        .generate()?;
+
+        let config_entries = config_entries(ident, data, &bean_name, prefix, ioc)?;
+
+        Ok(Registration {
+            ident,
+            key,
+            mod_ident,
+            bean_name,
+            build,
+            config_entries,
+            refresh,
+            watch,
+        })
+    }
+}
+
+impl Registration<'_> {
+    pub(crate) fn generate(&self, ioc: &TokenStream) -> TokenStream {
+        let Self {
+            ident,
+            ref key,
+            ref mod_ident,
+            ref bean_name,
+            ref build,
+            ref config_entries,
+            refresh,
+            watch,
+        } = *self;
+
+        // The `Registered` impl lives next to the struct, so that the key
+        // resolves against the user's imports.
+        let stored_path = if refresh {
+            quote! { ::#ioc::prelude::Refresh<#ident> }
+        } else {
+            quote! { #ident }
+        };
+
+        let (stored, store, refresh_method) = if refresh {
+            (
+                quote! { Refresh<#ident> },
+                quote! { Refresh::new(bean) },
+                quote! {
+                    #[distributed_slice(REFRESH_METHODS)]
+                    static REFRESH_METHOD: RefreshMethod = refresh_method;
+
+                    #[inline]
+                    fn refresh_method(ctx: &mut RefreshCtx<'_>) {
+                        if let Some(bean) = build(ctx) {
+                            ctx.stage(move |phase| PLACE.get(phase).store(bean));
+                        }
+                    }
+                },
+            )
+        } else {
+            (quote! { #ident }, quote! { bean }, quote! {})
+        };
+
+        let watch_method = if watch.is_empty() {
+            quote! {}
+        } else {
+            let bean = if refresh {
+                quote! { &*PLACE.get(phase).load() }
+            } else {
+                quote! { PLACE.get(phase) }
+            };
+            let keys = watch.iter().enumerate().map(|(index, key)| {
+                let name = format_ident!("WATCH_KEY_{}", index);
+                quote! {
+                    #[distributed_slice(WATCH_KEYS)]
+                    static #name: &str = #key;
+                }
+            });
+            quote! {
+                #(#keys)*
+
+                #[distributed_slice(WATCH_METHODS)]
+                static WATCH_METHOD: WatchMethod = watch_method;
+
+                #[inline]
+                fn watch_method(change: &mut ConfigChange<'_>) {
+                    change.watch(&[#(#watch),*], |phase, key, old, new| {
+                        OnConfigChange::on_config_change(#bean, key, old, new)
+                    });
+                }
+            }
+        };
+
+        quote! {
             pub mod #mod_ident {
                 use ::#ioc::prelude::*;
                 use ::linkme::distributed_slice;
                 use super::#ident;
 
-                static PLACE: StaticPlace<#ident> = StaticPlace::uninit();
+                const BEAN: &str = #bean_name;
+
+                pub(super) static PLACE: StaticPlace<#stored> = StaticPlace::uninit();
 
                 #[distributed_slice(INIT_METHODS)]
                 static INIT_METHOD: InitMethod = init_method;
 
+                #[inline]
+                fn build(ctx: &mut impl ConfigCollector) -> Option<#ident> {
+                    #build
+                }
+
                 #[inline]
                 fn init_method(ctx: &mut InitCtx) -> #ioc::Result<()> {
-                    let B = #build_method;
-                    PLACE.initialize(ctx).write(B);
+                    init(ctx).map_err(|err| ::#ioc::prelude::Error::bean_init(BEAN, err))
+                }
+
+                #[inline]
+                fn init(ctx: &mut InitCtx) -> #ioc::Result<()> {
+                    if let Some(bean) = build(ctx) {
+                        PLACE.initialize(ctx).write(#store);
+                    }
                     Ok(())
                 }
 
+                #refresh_method
+
+                #watch_method
+
                 #[distributed_slice(DROP_METHODS)]
                 static DROP_METHOD: DropMethod = drop_method;
 
@@ -159,24 +384,92 @@ impl Bean {
                         PLACE.deinitialize(phase)
                     }
                 }
+            }
 
-                unsafe impl Registered<#key> for Ctx {
-                    type Bean = #ident;
+            unsafe impl ::#ioc::prelude::Registered<#key> for ::#ioc::prelude::Ctx {
+                type Bean = #stored_path;
 
-                    #[inline(always)]
-                    fn get(ctx: &Ctx) -> &Self::Bean {
-                        PLACE.get(ctx)
-                    }
+                #[inline(always)]
+                fn get(ctx: &::#ioc::prelude::Ctx) -> &Self::Bean {
+                    ::#ioc::prelude::Place::get(&#mod_ident::PLACE, ctx)
+                }
 
-                    #[inline(always)]
-                    fn get_mut(ctx: &mut Ctx) -> &mut Self::Bean {
-                        PLACE.get_mut(ctx)
-                    }
+                #[inline(always)]
+                fn get_mut(ctx: &mut ::#ioc::prelude::Ctx) -> &mut Self::Bean {
+                    ::#ioc::prelude::Place::get_mut(&#mod_ident::PLACE, ctx)
                 }
             }
 
-            #alias_impl
+            #config_entries
+        }
+    }
+}
+
+/// Records the config fields outside the register module, so their types
+/// resolve against the imports of the bean's own module.
+fn config_entries(
+    ident: &Ident,
+    fields: &Data<(), Field>,
+    bean: &str,
+    prefix: Option<&str>,
+    ioc: &TokenStream,
+) -> Result<TokenStream> {
+    let Some(fields) = fields.as_ref().take_struct() else {
+        return Err(Error::unsupported_shape("only struct is supported").with_span(ident));
+    };
+
+    let entries = fields
+        .iter()
+        .enumerate()
+        .filter_map(|(index, f)| f.as_init(ident, index, prefix).entry(bean, ioc));
+
+    Ok(entry_statics(entries, ioc))
+}
+
+/// The `value = ...` expressions and `custom` validators of the fields as
+/// associated fns of the bean, shared by all of its registrations.
+fn value_fns(ident: &Ident, fields: &Data<(), Field>, ioc: &TokenStream) -> Result<TokenStream> {
+    let Some(fields) = fields.as_ref().take_struct() else {
+        return Err(Error::unsupported_shape("only struct is supported").with_span(ident));
+    };
+
+    let defs: Vec<_> = fields
+        .iter()
+        .enumerate()
+        .flat_map(|(index, f)| {
+            let init = f.as_init(ident, index, None);
+            [init.value_def(ioc), init.check_def()]
         })
+        .flatten()
+        .collect();
+
+    if defs.is_empty() {
+        return Ok(quote! {});
+    }
+    Ok(quote! {
+        impl #ident {
+            #(#defs)*
+        }
+    })
+}
+
+/// Adds each `ConfigEntry` expression to the `CONFIG_ENTRIES` slice.
+pub(crate) fn entry_statics(
+    entries: impl Iterator<Item = TokenStream>,
+    ioc: &TokenStream,
+) -> TokenStream {
+    let statics = entries.enumerate().map(|(index, entry)| {
+        let name = format_ident!("CONFIG_ENTRY_{}", index);
+        quote! {
+            #[::linkme::distributed_slice(::#ioc::prelude::CONFIG_ENTRIES)]
+            static #name: ::#ioc::prelude::ConfigEntry = #entry;
+        }
+    });
+
+    quote! {
+        const _: () = {
+            #(#statics)*
+        };
     }
 }
 
@@ -212,13 +505,13 @@ mod test {
         let parsed = parse_str(input).unwrap();
         let result = Bean::from_derive_input(&parsed);
         if let Err(err) = result {
-            println!("err 0:{}", err.write_errors().to_string());
+            println!("err 0:{}", err.write_errors());
             return;
         }
         let bean_struct = result.unwrap();
 
         if let Err(err) = bean_struct.generate() {
-            println!("err 1:{}", err.write_errors().to_string());
+            println!("err 1:{}", err.write_errors());
             return;
         }
 
@@ -241,13 +534,13 @@ mod test {
         let parsed = parse_str(input).unwrap();
         let result = Bean::from_derive_input(&parsed);
         if let Err(err) = result {
-            println!("err 0:{}", err.write_errors().to_string());
+            println!("err 0:{}", err.write_errors());
             return;
         }
         let bean_struct = result.unwrap();
 
         if let Err(err) = bean_struct.generate() {
-            println!("err 1:{}", err.write_errors().to_string());
+            println!("err 1:{}", err.write_errors());
             return;
         }
 
@@ -270,13 +563,13 @@ mod test {
         let parsed = parse_str(input).unwrap();
         let result = Bean::from_derive_input(&parsed);
         if let Err(err) = result {
-            println!("err 0:{}", err.write_errors().to_string());
+            println!("err 0:{}", err.write_errors());
             return;
         }
         let bean_struct = result.unwrap();
 
         if let Err(err) = bean_struct.generate() {
-            println!("err 1:{}", err.write_errors().to_string());
+            println!("err 1:{}", err.write_errors());
             return;
         }
 
@@ -303,13 +596,13 @@ mod test {
         let parsed = parse_str(input).unwrap();
         let result = Bean::from_derive_input(&parsed);
         if let Err(err) = result {
-            println!("err 0:{}", err.write_errors().to_string());
+            println!("err 0:{}", err.write_errors());
             return;
         }
         let bean_struct = result.unwrap();
 
         if let Err(err) = bean_struct.generate() {
-            println!("err 1:{}", err.write_errors().to_string());
+            println!("err 1:{}", err.write_errors());
             return;
         }
 
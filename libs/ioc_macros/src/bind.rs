@@ -1,16 +1,24 @@
-use darling::FromMeta;
-use syn::{Expr, Generics, Token, Type, TypeParamBound, parse_quote, punctuated::Punctuated};
+use darling::{FromMeta, ast::NestedMeta};
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::{
+    Expr, GenericParam, Generics, Ident, ImplItem, Item, Token, TraitItem, Type, TypeParamBound,
+    WherePredicate, parse_quote, punctuated::Punctuated,
+};
 
 #[derive(Debug, PartialEq, FromMeta)]
 pub(crate) struct Bean {
     #[darling(default)]
     key: Option<syn::Path>,
     path: syn::Path,
+    /// A `#[rivete(refresh)]` bean is stored, and registered, as `Refresh<T>`.
+    #[darling(default)]
+    refresh: bool,
 }
 
 impl Bean {
     pub(crate) fn add_bounds(&self, generics: &mut Generics) {
-        let bean: Type = {
+        let path: Type = {
             let path = &self.path;
             syn::parse_quote! { #path }
         };
@@ -18,9 +26,14 @@ impl Bean {
             if let Some(ref key) = self.key {
                 syn::parse_quote! { #key }
             } else {
-                bean.clone()
+                path.clone()
             }
         };
+        let bean: Type = if self.refresh {
+            syn::parse_quote! { Refresh<#path> }
+        } else {
+            path
+        };
 
         let where_clause = generics
             .where_clause
@@ -53,7 +66,27 @@ pub(crate) struct Trait {
     context: Option<syn::Path>,
 }
 impl Trait {
-    pub(crate) fn add_bounds(&self, generics: &mut Generics) {
+    /// The explicit `context`, or the generic parameter bounded by `Context`,
+    /// looked up on the method first and then on its impl block or trait.
+    fn context(&self, generics: &Generics, outer: Option<&Generics>) -> syn::Result<Option<Type>> {
+        if let Some(ref ctx_type) = self.context {
+            return Ok(Some(syn::parse_quote! { #ctx_type }));
+        }
+        let param = match context_param(generics)? {
+            Some(param) => Some(param),
+            None => outer.map(context_param).transpose()?.flatten(),
+        };
+        Ok(param.map(|param| syn::parse_quote! { #param }))
+    }
+
+    /// With the `outer` generics of an impl block or trait, a method without a
+    /// context is left alone instead of rejected, so constructors still compile.
+    /// Returns whether the bounds were added.
+    pub(crate) fn add_bounds(
+        &self,
+        generics: &mut Generics,
+        outer: Option<&Generics>,
+    ) -> syn::Result<bool> {
         let name: Type = {
             let name = &self.name;
             syn::parse_quote! { #name }
@@ -61,10 +94,19 @@ impl Trait {
 
         let trait_bound = &self.traits.0;
 
-        let context: Type = if let Some(ref ctx_type) = self.context {
-            syn::parse_quote! { #ctx_type }
-        } else {
-            syn::parse_quote! { C }
+        let context = match self.context(generics, outer)? {
+            Some(context) => context,
+            None if outer.is_some() => return Ok(false),
+            None => {
+                return Err(syn::Error::new_spanned(
+                    &self.name,
+                    format!(
+                        "cannot infer the context of alias `{}`, add a generic parameter bounded \
+                         by `Context` or set `context = ...`",
+                        quote::quote!(#name)
+                    ),
+                ));
+            }
         };
 
         let where_clause = generics
@@ -81,20 +123,160 @@ impl Trait {
         where_clause.predicates.push(syn::parse_quote! {
             Ctx: Registered<<#context as Alias<#name>>::Key, Bean: #trait_bound>
         });
+        Ok(true)
     }
 }
 
+fn is_context(bound: &TypeParamBound) -> bool {
+    matches!(bound, TypeParamBound::Trait(bound)
+        if bound.path.segments.last().is_some_and(|s| s.ident == "Context"))
+}
+
+/// Finds the type parameter bounded by `Context`, inline or in the where-clause.
+fn context_param(generics: &Generics) -> syn::Result<Option<&Ident>> {
+    let bounded_in_where = |ident: &Ident| {
+        generics
+            .where_clause
+            .iter()
+            .flat_map(|w| &w.predicates)
+            .any(|predicate| {
+                matches!(predicate, WherePredicate::Type(predicate)
+                if matches!(&predicate.bounded_ty, Type::Path(ty) if ty.path.is_ident(ident))
+                    && predicate.bounds.iter().any(is_context))
+            })
+    };
+
+    let mut params = generics.params.iter().filter_map(|param| match param {
+        GenericParam::Type(param)
+            if param.bounds.iter().any(is_context) || bounded_in_where(&param.ident) =>
+        {
+            Some(&param.ident)
+        }
+        _ => None,
+    });
+
+    let first = params.next();
+    if let Some(second) = params.next() {
+        return Err(syn::Error::new_spanned(
+            second,
+            format!(
+                "ambiguous context: `{}` and `{second}` are both bound by `Context`, \
+                 pick one with `context = ...`",
+                first.unwrap()
+            ),
+        ));
+    }
+    Ok(first)
+}
+
 #[derive(Debug, PartialEq, FromMeta)]
-#[darling(derive_syn_parse)]
 pub(crate) enum Bind {
     Bean(Bean),
     Alias(Trait),
 }
 impl Bind {
-    pub(crate) fn add_bounds(&self, generics: &mut Generics) {
+    pub(crate) fn add_bounds(
+        &self,
+        generics: &mut Generics,
+        outer: Option<&Generics>,
+    ) -> syn::Result<bool> {
         match self {
-            Bind::Bean(bean) => bean.add_bounds(generics),
-            Bind::Alias(trait_) => trait_.add_bounds(generics),
+            Bind::Bean(bean) => {
+                bean.add_bounds(generics);
+                Ok(true)
+            }
+            Bind::Alias(trait_) => trait_.add_bounds(generics, outer),
+        }
+    }
+}
+
+/// The bindings of one `#[with(...)]`, e.g. `bean(path = B), alias(name = AKey, traits = A)`.
+#[derive(Debug, PartialEq)]
+pub(crate) struct With(Vec<Bind>);
+
+impl With {
+    pub(crate) fn parse(attr: TokenStream) -> darling::Result<Self> {
+        let mut errors = darling::Error::accumulator();
+        let binds = NestedMeta::parse_meta_list(attr)?
+            .iter()
+            .filter_map(|item| errors.handle(Bind::from_list(std::slice::from_ref(item))))
+            .collect();
+        errors.finish_with(Self(binds))
+    }
+
+    /// Marks in `applied` the bindings whose bounds were added.
+    fn add_bounds(
+        &self,
+        generics: &mut Generics,
+        outer: Option<&Generics>,
+        applied: &mut [bool],
+    ) -> syn::Result<()> {
+        let mut errors: Option<syn::Error> = None;
+        for (bind, applied) in self.0.iter().zip(applied) {
+            match bind.add_bounds(generics, outer) {
+                Ok(added) => *applied |= added,
+                Err(e) => match &mut errors {
+                    Some(errors) => errors.combine(e),
+                    None => errors = Some(e),
+                },
+            }
+        }
+        errors.map_or(Ok(()), Err)
+    }
+
+    /// Adds the bounds to a function, or to every method of an impl block or trait.
+    /// Aliases skip the methods that take no context, but must apply to one of them.
+    pub(crate) fn expand(&self, mut item: Item) -> syn::Result<TokenStream> {
+        let mut applied = vec![false; self.0.len()];
+        match &mut item {
+            Item::Fn(item) => self.add_bounds(&mut item.sig.generics, None, &mut applied)?,
+            Item::Impl(item) => {
+                for method in &mut item.items {
+                    if let ImplItem::Fn(method) = method {
+                        self.add_bounds(
+                            &mut method.sig.generics,
+                            Some(&item.generics),
+                            &mut applied,
+                        )?;
+                    }
+                }
+            }
+            Item::Trait(item) => {
+                for method in &mut item.items {
+                    if let TraitItem::Fn(method) = method {
+                        self.add_bounds(
+                            &mut method.sig.generics,
+                            Some(&item.generics),
+                            &mut applied,
+                        )?;
+                    }
+                }
+            }
+            item => {
+                return Err(syn::Error::new_spanned(
+                    item,
+                    "`with` applies to functions, impl blocks and traits",
+                ));
+            }
+        }
+        let unused = self
+            .0
+            .iter()
+            .zip(applied)
+            .filter_map(|(bind, applied)| match bind {
+                Bind::Alias(trait_) if !applied => Some(syn::Error::new_spanned(
+                    &trait_.name,
+                    "alias applies to no method, none takes a generic parameter bounded by \
+                 `Context`, add one or set `context = ...`",
+                )),
+                _ => None,
+            });
+        match unused.reduce(|mut errors, e| {
+            errors.combine(e);
+            errors
+        }) {
+            Some(errors) => Err(errors),
+            None => Ok(item.into_token_stream()),
         }
     }
 }
@@ -117,7 +299,7 @@ mod tests {
 
     #[test]
     fn test_bind() {
-        let bean: Attribute = parse_quote!( #[with(bean = B, key = BKey)] );
+        let bean: Attribute = parse_quote!( #[with(path = B, key = BKey)] );
 
         let bean = Bean::from_meta(&bean.meta).unwrap();
         assert_eq!(
@@ -125,21 +307,199 @@ mod tests {
             Bean {
                 path: parse_quote!(B),
                 key: Some(parse_quote!(BKey)),
+                refresh: false,
             }
         );
     }
 
+    #[test]
+    fn test_bind_refresh() {
+        let with = With::parse(quote::quote! { bean(path = B, refresh) }).unwrap();
+        let expanded = with
+            .expand(parse_quote! { fn f<C: Context>(ctx: &C) {} })
+            .unwrap();
+        let expected: syn::ItemFn = parse_quote! {
+            fn f<C: Context>(ctx: &C) where Ctx: Registered<B, Bean = Refresh<B>> {}
+        };
+        assert_eq!(syn::parse2::<syn::ItemFn>(expanded).unwrap(), expected);
+    }
+
     #[test]
     fn test_trait() {
-        let bean: Attribute = parse_quote!( #[with(name = AKey, traits = A)] );
+        let alias: Attribute = parse_quote!( #[with(name = AKey, traits = A)] );
 
-        let bean = Bean::from_meta(&bean.meta).unwrap();
+        let alias = Trait::from_meta(&alias.meta).unwrap();
         assert_eq!(
-            bean,
-            Bean {
-                path: parse_quote!(B),
-                key: Some(parse_quote!(BKey)),
+            alias,
+            Trait {
+                name: parse_quote!(AKey),
+                traits: Bounds(parse_quote!(A)),
+                context: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_with_list() {
+        let with = With::parse(quote::quote! {
+            bean(path = B), alias(name = AKey, traits = A), bean(path = D, key = DKey)
+        })
+        .unwrap();
+        assert_eq!(
+            with,
+            With(vec![
+                Bind::Bean(Bean {
+                    path: parse_quote!(B),
+                    key: None,
+                    refresh: false,
+                }),
+                Bind::Alias(Trait {
+                    name: parse_quote!(AKey),
+                    traits: Bounds(parse_quote!(A)),
+                    context: None,
+                }),
+                Bind::Bean(Bean {
+                    path: parse_quote!(D),
+                    key: Some(parse_quote!(DKey)),
+                    refresh: false,
+                }),
+            ])
+        );
+
+        assert!(With::parse(quote::quote! { bean(path = B), other(x = 1) }).is_err());
+    }
+
+    #[test]
+    fn test_with_impl() {
+        let with = With::parse(quote::quote! { bean(path = B) }).unwrap();
+        let item: Item = parse_quote! {
+            impl S {
+                const N: usize = 1;
+                fn a<C: Context>(&self, ctx: &C) {}
+                fn b(&self) where Self: Sized {}
             }
+        };
+        let expanded: syn::ItemImpl = syn::parse2(with.expand(item).unwrap()).unwrap();
+        let expected: syn::ItemImpl = parse_quote! {
+            impl S {
+                const N: usize = 1;
+                fn a<C: Context>(&self, ctx: &C) where Ctx: Registered<B, Bean = B> {}
+                fn b(&self) where Self: Sized, Ctx: Registered<B, Bean = B> {}
+            }
+        };
+        assert_eq!(expanded, expected);
+
+        let item: Item = parse_quote! { struct S; };
+        assert!(with.expand(item).is_err());
+    }
+
+    fn expand_fn(attr: TokenStream, item: syn::ItemFn) -> syn::Result<syn::ItemFn> {
+        let with = With::parse(attr).unwrap();
+        syn::parse2(with.expand(Item::Fn(item))?)
+    }
+
+    #[test]
+    fn test_alias_context() {
+        let attr = quote::quote! { alias(name = AKey, traits = A) };
+
+        let expanded = expand_fn(
+            attr.clone(),
+            parse_quote! { fn f<T, Cxx>(ctx: &Cxx) where Cxx: ioc::Context {} },
+        )
+        .unwrap();
+        let expected: syn::ItemFn = parse_quote! {
+            fn f<T, Cxx>(ctx: &Cxx)
+            where
+                Cxx: ioc::Context,
+                Cxx: Alias<AKey>,
+                Ctx: Registered<<Cxx as Alias<AKey>>::Key, Bean: A>
+            {}
+        };
+        assert_eq!(expanded, expected);
+
+        let err = expand_fn(attr.clone(), parse_quote! { fn f(ctx: &Ctx) {} }).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("cannot infer the context of alias `AKey`")
+        );
+
+        let err = expand_fn(
+            attr.clone(),
+            parse_quote! { fn f<C: Context, D>(c: &C, d: &D) where D: Context {} },
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("ambiguous context: `C` and `D`")
         );
+
+        let explicit = quote::quote! { alias(name = AKey, traits = A, context = D) };
+        let expanded = expand_fn(
+            explicit,
+            parse_quote! { fn f<C: Context, D: Context>(c: &C, d: &D) {} },
+        )
+        .unwrap();
+        let expected: syn::ItemFn = parse_quote! {
+            fn f<C: Context, D: Context>(c: &C, d: &D)
+            where
+                D: Alias<AKey>,
+                Ctx: Registered<<D as Alias<AKey>>::Key, Bean: A>
+            {}
+        };
+        assert_eq!(expanded, expected);
+
+        let with = With::parse(attr).unwrap();
+        let item: Item = parse_quote! {
+            impl S {
+                fn new() -> Self { S }
+                fn get<C: Context>(&self, ctx: &C) {}
+            }
+        };
+        assert!(with.expand(item).is_ok());
+
+        let item: Item = parse_quote! {
+            impl S {
+                fn new() -> Self { S }
+            }
+        };
+        let err = with.expand(item).unwrap_err();
+        assert!(err.to_string().starts_with("alias applies to no method"));
+    }
+
+    #[test]
+    fn test_alias_outer_context() {
+        let with = With::parse(quote::quote! { alias(name = AKey, traits = A) }).unwrap();
+        let item: Item = parse_quote! {
+            impl<C: Context> S<C> {
+                fn new(ctx: C) -> Self { S(ctx) }
+            }
+        };
+        let expanded: syn::ItemImpl = syn::parse2(with.expand(item).unwrap()).unwrap();
+        let expected: syn::ItemImpl = parse_quote! {
+            impl<C: Context> S<C> {
+                fn new(ctx: C) -> Self
+                where
+                    C: Alias<AKey>,
+                    Ctx: Registered<<C as Alias<AKey>>::Key, Bean: A>
+                { S(ctx) }
+            }
+        };
+        assert_eq!(expanded, expected);
+
+        let item: Item = parse_quote! {
+            trait T<C> where C: Context {
+                fn get(&self, ctx: &C);
+            }
+        };
+        let expanded: syn::ItemTrait = syn::parse2(with.expand(item).unwrap()).unwrap();
+        let expected: syn::ItemTrait = parse_quote! {
+            trait T<C> where C: Context {
+                fn get(&self, ctx: &C)
+                where
+                    C: Alias<AKey>,
+                    Ctx: Registered<<C as Alias<AKey>>::Key, Bean: A>;
+            }
+        };
+        assert_eq!(expanded, expected);
     }
 }
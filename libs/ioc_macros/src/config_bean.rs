@@ -0,0 +1,246 @@
+use crate::bean::{Registration, config_default, entry_statics, resolve_ioc_crate};
+use darling::{Error, FromDeriveInput, FromField, Result, ast::Data};
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::{Lit, Path, Type, parse_quote};
+
+/// A settings struct read from config as a whole, see `#[derive(Config)]`.
+#[derive(Debug, FromDeriveInput)]
+#[darling(attributes(rivete), supports(struct_named))]
+pub(crate) struct ConfigStruct {
+    ident: Ident,
+
+    data: Data<(), ConfigField>,
+
+    #[darling(default)]
+    ioc_crate: Option<Path>,
+}
+
+/// A settings struct registered as a bean, see `#[derive(ConfigBean)]`.
+#[derive(Debug, FromDeriveInput)]
+#[darling(attributes(rivete), supports(struct_named))]
+pub(crate) struct ConfigBean {
+    ident: Ident,
+
+    data: Data<(), ConfigField>,
+
+    /// The config subtree the bean is read from.
+    #[darling(default)]
+    prefix: Option<String>,
+    #[darling(default)]
+    ioc_crate: Option<Path>,
+
+    #[darling(default)]
+    refresh: bool,
+    #[darling(multiple)]
+    watch: Vec<String>,
+}
+
+#[derive(Debug, FromField)]
+#[darling(attributes(rivete))]
+pub(crate) struct ConfigField {
+    ident: Option<Ident>,
+    ty: Type,
+
+    /// The key relative to the struct, the field name by default.
+    #[darling(default)]
+    name: Option<String>,
+    #[darling(default)]
+    default: Option<Lit>,
+}
+
+impl ConfigField {
+    fn name(&self) -> String {
+        match (&self.name, &self.ident) {
+            (Some(name), _) => name.clone(),
+            (None, Some(ident)) => ident.to_string(),
+            (None, None) => unreachable!("only named fields are supported"),
+        }
+    }
+}
+
+fn struct_fields(data: &Data<(), ConfigField>) -> Vec<&ConfigField> {
+    match data {
+        Data::Struct(fields) => fields.iter().collect(),
+        Data::Enum(_) => unreachable!("only structs are supported"),
+    }
+}
+
+/// Implements `FromConfig` through the cfg-rs derive, applied to a private
+/// copy of the fields that is then moved into the struct.
+fn impl_from_config(ident: &Ident, fields: &[&ConfigField], ioc: &TokenStream) -> TokenStream {
+    let idents: Vec<_> = fields.iter().map(|field| &field.ident).collect();
+    let copies = fields.iter().map(|field| {
+        let ConfigField { ident, ty, .. } = field;
+        let name = field.name();
+        let default = field
+            .default
+            .as_ref()
+            .map(|lit| quote! { , default = #lit });
+        quote! {
+            #[config(name = #name #default)]
+            #ident: #ty
+        }
+    });
+
+    quote! {
+        const _: () = {
+            use ::#ioc::cfg_rs::{ConfigContext, ConfigError, ConfigValue, FromConfig};
+            use ::core::option::Option;
+            use ::core::result::Result;
+
+            #[derive(::#ioc::cfg_rs::FromConfig)]
+            struct Fields {
+                #(#copies,)*
+            }
+
+            impl FromConfig for #ident {
+                fn from_config(
+                    context: &mut ConfigContext<'_>,
+                    value: Option<ConfigValue<'_>>,
+                ) -> Result<Self, ConfigError> {
+                    let Fields { #(#idents),* } = FromConfig::from_config(context, value)?;
+                    Ok(Self { #(#idents),* })
+                }
+            }
+        };
+    }
+}
+
+impl ConfigStruct {
+    /// Implements `FromConfig`, reading each field below the struct's key.
+    pub(crate) fn impl_from_config(&self) -> Result<TokenStream> {
+        let ioc = resolve_ioc_crate(&self.ioc_crate)?;
+        Ok(impl_from_config(
+            &self.ident,
+            &struct_fields(&self.data),
+            &ioc,
+        ))
+    }
+}
+
+impl ConfigBean {
+    /// Implements `FromConfig` and registers the struct as a bean read from
+    /// its `prefix`.
+    pub(crate) fn generate(&self) -> Result<TokenStream> {
+        let ioc = resolve_ioc_crate(&self.ioc_crate)?;
+        let ident = &self.ident;
+        let Some(prefix) = &self.prefix else {
+            return Err(
+                Error::custom("a ConfigBean needs `#[rivete(prefix = \"...\")]`").with_span(ident),
+            );
+        };
+        let bean_name = ident.to_string();
+        let fields = struct_fields(&self.data);
+
+        let entries = fields.iter().map(|field| {
+            let name = field.name();
+            let key = format!("{prefix}.{name}");
+            let ty = &field.ty;
+            let default = match &field.default {
+                Some(lit) => config_default(&parse_quote!(#lit), &ioc),
+                None => quote! { None },
+            };
+            quote! {
+                ::#ioc::prelude::ConfigEntry {
+                    bean: #bean_name,
+                    field: #name,
+                    key: #key,
+                    ty: ::core::any::type_name::<#ty>,
+                    secret: || {
+                        use ::#ioc::prelude::{IsSecret as _, NotSecret as _};
+                        (&::#ioc::prelude::SecretProbe::<#ty>::NEW).is_secret()
+                    },
+                    default: #default,
+                }
+            }
+        });
+
+        let registration = Registration {
+            ident,
+            key: quote! { #ident },
+            mod_ident: format_ident!("{}_bean_register", bean_name.to_lowercase()),
+            bean_name: bean_name.clone(),
+            build: quote! {
+                let value = ctx.get_config::<#ident>(#prefix);
+                ctx.config_struct(BEAN, #prefix, value)
+            },
+            config_entries: entry_statics(entries, &ioc),
+            refresh: self.refresh,
+            watch: &self.watch,
+        };
+
+        let from_config = impl_from_config(ident, &fields, &ioc);
+        let register = registration.generate(&ioc);
+
+        Ok(quote! {
+            #from_config
+
+            #register
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn fields() {
+        let input: syn::DeriveInput = parse_quote! {
+            #[rivete(prefix = "server")]
+            struct Server {
+                host: String,
+                #[rivete(default = 8080)]
+                port: u16,
+                #[rivete(name = "tls.cert")]
+                cert: Option<String>,
+            }
+        };
+        let config = ConfigBean::from_derive_input(&input).unwrap();
+        assert_eq!(config.prefix.as_deref(), Some("server"));
+        let names: Vec<_> = struct_fields(&config.data)
+            .iter()
+            .map(|f| f.name())
+            .collect();
+        assert_eq!(names, ["host", "port", "tls.cert"]);
+        assert_eq!(
+            struct_fields(&config.data)[1].default,
+            Some(parse_quote!(8080))
+        );
+    }
+
+    #[test]
+    fn bean_needs_prefix() {
+        let input: syn::DeriveInput = parse_quote! {
+            struct Server {
+                host: String,
+            }
+        };
+        let config = ConfigBean::from_derive_input(&input).unwrap();
+        assert!(config.generate().is_err());
+    }
+
+    #[test]
+    fn config_rejects_bean_options() {
+        for input in [
+            parse_quote! {
+                #[rivete(prefix = "server")]
+                struct Server { host: String }
+            },
+            parse_quote! {
+                #[rivete(refresh)]
+                struct Server { host: String }
+            },
+            parse_quote! {
+                #[rivete(watch = "server.host")]
+                struct Server { host: String }
+            },
+        ] {
+            let input: syn::DeriveInput = input;
+            let err = ConfigStruct::from_derive_input(&input).unwrap_err();
+            assert!(err.to_string().starts_with("Unknown field"), "{err}");
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use darling::{FromMeta, ast::NestedMeta};
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{
+    FnArg, GenericParam, Ident, ItemFn, Pat, Path, Token, Type, TypeParamBound, TypeReference,
+    parse_quote, punctuated::Punctuated,
+};
+
+use crate::bean::resolve_ioc_crate;
+
+/// How an argument of an `#[inject]` function is provided.
+enum Arg {
+    /// `#[bean] b: &B`, looked up by key.
+    Bean { pat: Box<Pat>, ty: Type },
+    /// `#[alias(AKey)] a: &impl A`, looked up through the context's alias.
+    Alias {
+        pat: Box<Pat>,
+        name: Path,
+        bounds: Punctuated<TypeParamBound, Token![+]>,
+        ty: Option<Type>,
+    },
+    /// Anything unmarked stays a parameter of the wrapper.
+    Pass(FnArg),
+}
+
+impl Arg {
+    fn parse(arg: FnArg) -> syn::Result<Self> {
+        let FnArg::Typed(mut typed) = arg else {
+            return Err(syn::Error::new_spanned(
+                arg,
+                "`inject` applies to free functions",
+            ));
+        };
+
+        let mut alias = None;
+        let mut bean = false;
+        let mut attrs = Vec::with_capacity(typed.attrs.len());
+        for attr in typed.attrs.drain(..) {
+            if attr.path().is_ident("alias") {
+                alias = Some(attr.parse_args::<Path>()?);
+            } else if attr.path().is_ident("bean") {
+                attr.meta.require_path_only()?;
+                bean = true;
+            } else {
+                attrs.push(attr);
+            }
+        }
+        typed.attrs = attrs;
+
+        if bean && alias.is_some() {
+            return Err(syn::Error::new_spanned(
+                &typed.pat,
+                "an argument is either a `#[bean]` or an `#[alias(..)]`",
+            ));
+        }
+        let reference = match &*typed.ty {
+            _ if !bean && alias.is_none() => return Ok(Arg::Pass(FnArg::Typed(typed))),
+            Type::Reference(reference) => reference,
+            _ if bean => {
+                return Err(syn::Error::new_spanned(
+                    &typed.ty,
+                    "bean arguments are references, take `&T`",
+                ));
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &typed.ty,
+                    "alias arguments are `&impl Trait` or `&dyn Trait`",
+                ));
+            }
+        };
+        if let Some(mutability) = reference.mutability {
+            return Err(syn::Error::new_spanned(
+                mutability,
+                "injected beans are shared, take `&` instead of `&mut`",
+            ));
+        }
+        let TypeReference { elem, .. } = reference;
+
+        match (alias, &**elem) {
+            (Some(name), Type::ImplTrait(bound)) => Ok(Arg::Alias {
+                pat: typed.pat,
+                name,
+                bounds: bound.bounds.clone(),
+                ty: None,
+            }),
+            (Some(name), Type::TraitObject(bound)) => Ok(Arg::Alias {
+                pat: typed.pat,
+                name,
+                bounds: bound.bounds.clone(),
+                ty: Some(parse_quote! { &#elem }),
+            }),
+            (Some(_), _) => Err(syn::Error::new_spanned(
+                &typed.ty,
+                "alias arguments are `&impl Trait` or `&dyn Trait`",
+            )),
+            (None, Type::ImplTrait(_) | Type::TraitObject(_)) => Err(syn::Error::new_spanned(
+                &typed.ty,
+                "trait arguments are injected through an alias, use `#[alias(Name)]`",
+            )),
+            (None, _) => Ok(Arg::Bean {
+                pat: typed.pat,
+                ty: (**elem).clone(),
+            }),
+        }
+    }
+}
+
+/// The arguments of `#[inject(...)]`.
+#[derive(Debug, Default, FromMeta)]
+pub(crate) struct Inject {
+    #[darling(default)]
+    ioc_crate: Option<Path>,
+}
+
+impl Inject {
+    pub(crate) fn parse(attr: TokenStream) -> darling::Result<Self> {
+        Self::from_list(&NestedMeta::parse_meta_list(attr)?)
+    }
+
+    /// Rewrites `fn handle(#[bean] b: &B, input: Req)` into
+    /// `fn handle<C: Context>(ctx: &C, input: Req)`, resolving the injected
+    /// arguments from the context before the original body.
+    pub(crate) fn expand(&self, item: ItemFn) -> syn::Result<TokenStream> {
+        let ioc = resolve_ioc_crate(&self.ioc_crate)
+            .map_err(|e| syn::Error::new(Span::call_site(), e))?;
+        expand(&ioc, item)
+    }
+}
+
+fn expand(ioc: &TokenStream, mut item: ItemFn) -> syn::Result<TokenStream> {
+    let context: Ident = parse_quote!(__IocCtx);
+    let ctx: Ident = parse_quote!(__ioc_ctx);
+
+    let mut errors: Option<syn::Error> = None;
+    let mut inputs = Punctuated::<FnArg, Token![,]>::new();
+    inputs.push(parse_quote! { #ctx: &#context });
+    let mut lets = Vec::new();
+    let where_clause = item.sig.generics.make_where_clause();
+    for arg in std::mem::take(&mut item.sig.inputs) {
+        match Arg::parse(arg) {
+            Ok(Arg::Bean { pat, ty }) => {
+                where_clause.predicates.push(parse_quote! {
+                    ::#ioc::prelude::Ctx: ::#ioc::prelude::Registered<#ty, Bean = #ty>
+                });
+                lets.push(quote! {
+                    let #pat: &#ty = ::#ioc::prelude::Context::get_by_key::<#ty>(#ctx);
+                });
+            }
+            Ok(Arg::Alias {
+                pat,
+                name,
+                bounds,
+                ty,
+            }) => {
+                where_clause.predicates.push(parse_quote! {
+                    #context: ::#ioc::prelude::Alias<#name>
+                });
+                where_clause.predicates.push(parse_quote! {
+                    ::#ioc::prelude::Ctx: ::#ioc::prelude::Registered<
+                        <#context as ::#ioc::prelude::Alias<#name>>::Key,
+                        Bean: #bounds
+                    >
+                });
+                let ty = ty.map(|ty| quote! { : #ty });
+                lets.push(quote! {
+                    let #pat #ty = ::#ioc::prelude::Context::get_by_alias::<#name>(#ctx);
+                });
+            }
+            Ok(Arg::Pass(arg)) => inputs.push(arg),
+            Err(e) => match &mut errors {
+                Some(errors) => errors.combine(e),
+                None => errors = Some(e),
+            },
+        }
+    }
+    if let Some(errors) = errors {
+        return Err(errors);
+    }
+
+    item.sig.inputs = inputs;
+    item.sig.generics.params.push(GenericParam::Type(
+        parse_quote! { #context: ::#ioc::prelude::Context },
+    ));
+
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = item;
+    Ok(quote! {
+        #(#attrs)*
+        #vis #sig {
+            #(#lets)*
+            #block
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(item: ItemFn) -> syn::Result<TokenStream> {
+        Inject::parse(quote! { ioc_crate = "ioc" })
+            .unwrap()
+            .expand(item)
+    }
+
+    fn expand_str(item: ItemFn) -> syn::Result<String> {
+        expand(item).map(|tokens| tokens.to_string())
+    }
+
+    #[test]
+    fn test_inject() {
+        let expanded: ItemFn = syn::parse2(
+            expand(parse_quote! {
+                pub fn handle(#[bean] b: &B, #[alias(AKey)] a: &impl A, input: Req, name: &str) -> u8 {
+                    0
+                }
+            })
+            .unwrap(),
+        )
+        .unwrap();
+        let expected: ItemFn = parse_quote! {
+            pub fn handle<__IocCtx: ::ioc::prelude::Context>(
+                __ioc_ctx: &__IocCtx,
+                input: Req,
+                name: &str
+            ) -> u8
+            where
+                ::ioc::prelude::Ctx: ::ioc::prelude::Registered<B, Bean = B>,
+                __IocCtx: ::ioc::prelude::Alias<AKey>,
+                ::ioc::prelude::Ctx: ::ioc::prelude::Registered<
+                    <__IocCtx as ::ioc::prelude::Alias<AKey>>::Key,
+                    Bean: A
+                >
+            {
+                let b: &B = ::ioc::prelude::Context::get_by_key::<B>(__ioc_ctx);
+                let a = ::ioc::prelude::Context::get_by_alias::<AKey>(__ioc_ctx);
+                {
+                    0
+                }
+            }
+        };
+        assert_eq!(expanded, expected);
+    }
+
+    #[test]
+    fn test_inject_errors() {
+        let err = expand_str(parse_quote! { fn f(&self, #[bean] b: &B) {} }).unwrap_err();
+        assert_eq!(err.to_string(), "`inject` applies to free functions");
+
+        let err = expand_str(parse_quote! { fn f(#[bean] b: &mut B) {} }).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "injected beans are shared, take `&` instead of `&mut`"
+        );
+
+        let err = expand_str(parse_quote! { fn f(#[bean] a: &impl A) {} }).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "trait arguments are injected through an alias, use `#[alias(Name)]`"
+        );
+
+        let err = expand_str(parse_quote! { fn f(#[bean] b: B) {} }).unwrap_err();
+        assert_eq!(err.to_string(), "bean arguments are references, take `&T`");
+
+        let err =
+            expand_str(parse_quote! { fn f(#[bean] #[alias(AKey)] a: &impl A) {} }).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "an argument is either a `#[bean]` or an `#[alias(..)]`"
+        );
+
+        let err = expand_str(parse_quote! { fn f(#[alias(AKey)] a: &B) {} }).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "alias arguments are `&impl Trait` or `&dyn Trait`"
+        );
+    }
+}
@@ -1,26 +1,57 @@
 mod bean;
 mod bind;
+mod config_bean;
 mod context;
+mod inject;
 
 use darling::FromDeriveInput;
 use proc_macro::TokenStream;
 use quote::ToTokens;
 use syn::{DeriveInput, parse_macro_input};
 
+/// Adds the `where` bounds a function needs to read beans from its context,
+/// on a function, or on every method of an impl block or trait.
+///
+/// `bean(path = T)` binds the bean `T`, `bean(path = T, key = K)` one
+/// registered under `K`, and `bean(path = T, refresh)` a `#[rivete(refresh)]`
+/// bean, which is registered as `Refresh<T>`. `alias(name = N, traits = A)`
+/// binds the bean the context selects for the alias `N`.
 #[proc_macro_attribute]
 pub fn with(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let bind: bind::Bind = match syn::parse(attr) {
+    let with = match bind::With::parse(attr.into()) {
         Ok(v) => v,
         Err(e) => {
-            return e.to_compile_error().into();
+            return e.write_errors().into();
         }
     };
 
-    let mut input = syn::parse_macro_input!(item as syn::ItemFn);
+    let input = syn::parse_macro_input!(item as syn::Item);
 
-    bind.add_bounds(&mut input.sig.generics);
+    match with.expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Turns a free function into one taking any `Context` in place of its
+/// injected arguments: `#[bean] b: &B` is read by key and
+/// `#[alias(N)] a: &impl A` through the alias `N`. Unmarked arguments stay
+/// parameters of the function.
+#[proc_macro_attribute]
+pub fn inject(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let inject = match inject::Inject::parse(attr.into()) {
+        Ok(v) => v,
+        Err(e) => {
+            return e.write_errors().into();
+        }
+    };
 
-    input.into_token_stream().into()
+    let input = syn::parse_macro_input!(item as syn::ItemFn);
+
+    match inject.expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
 }
 
 #[proc_macro_derive(Bean, attributes(rivete))]
@@ -32,7 +63,25 @@ pub fn bean(input: TokenStream) -> TokenStream {
     }
 }
 
+#[proc_macro_derive(Config, attributes(rivete))]
+pub fn config(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match config_bean::ConfigStruct::from_derive_input(&input).and_then(|c| c.impl_from_config()) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.write_errors().into(),
+    }
+}
+
+#[proc_macro_derive(ConfigBean, attributes(rivete))]
+pub fn config_bean(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match config_bean::ConfigBean::from_derive_input(&input).and_then(|c| c.generate()) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.write_errors().into(),
+    }
+}
+
 #[proc_macro_derive(Context, attributes(rivete))]
-pub fn context(input: TokenStream) -> TokenStream {
+pub fn context(_input: TokenStream) -> TokenStream {
     unimplemented!()
 }